@@ -2,11 +2,18 @@
 use clap::Parser;
 use we_clap::WeParser; // Wrapper for clap Parser
 
+use std::path::Path;
+use std::sync::Arc;
+
 // own lib:
 use osm_tb::*;
 
+static TERRAIN_COLOR: [f32; 4] = [0.59, 1.0, 0.59, 1.0]; // like the flat ground
+static COMPLETION_PASSES: usize = 3; // the ways of a relation, then their nodes
+static TOKEN_VARIABLE: &str = "OSM_TB_TOKEN";
+
 // https://crates.io/crates/we_clap
-#[derive(Parser, Debug, Default, Clone, Copy)]
+#[derive(Parser, Debug, Default, Clone)]
 #[command(about = "OBI args: way or relation, area, only(show_me)", version, long_about = None)]
 pub struct UrlClArgs {
     // Westminster 367642719, Abbey: 364313092
@@ -20,6 +27,61 @@ pub struct UrlClArgs {
     pub only: u64,
     #[arg(short, long, default_value = "0")]
     pub area: u32,
+    // Windows and level lines on the walls
+    #[arg(short, long)]
+    pub facade: bool,
+    // Read the tags like: obi, s3db, f4map or osm2world
+    #[arg(short, long, default_value = "obi")]
+    pub profile: InterpretationProfile,
+    // Load all profiles, key V switches between them
+    #[arg(short, long)]
+    pub compare: bool,
+    // A directory with SRTM .hgt tiles. The buildings stand on that terrain
+    #[arg(short, long)]
+    pub dem: Option<String>,
+    // Also write a plan view of the buildings and parts to this SVG file
+    #[arg(short, long)]
+    pub svg: Option<String>,
+    // Render by the CPU to this PNG file, no window is opened. For machines without a GPU
+    #[arg(long)]
+    pub snapshot: Option<String>,
+    // Print problems of the meshes: open edges, inverted or degenerate triangles, z-fighting
+    #[arg(long)]
+    pub check: bool,
+    // Also load the nodes and relation members outside of the range
+    #[arg(long)]
+    pub complete: bool,
+    // A directory to keep the API responses in. Unchanged elements are not loaded again
+    #[arg(long)]
+    pub cache: Option<String>,
+    // Load all responses again and overwrite the cached ones
+    #[arg(long)]
+    pub refresh: bool,
+    // Only use the cached responses, like fixtures, no requests
+    #[arg(long)]
+    pub offline: bool,
+    // Another API, like https://master.apis.dev.openstreetmap.org/api/0.6/ or a local mock server
+    #[arg(long)]
+    pub api: Option<String>,
+    // A directory with way.json or relation.json and bbox.json, like assets, instead of the API
+    #[arg(long)]
+    pub fixtures: Option<String>,
+    // The element as it was: latest, a version or a date like 2023-05-22. Repeat it to
+    // compare the versions, key V switches between them
+    #[arg(long)]
+    pub as_of: Vec<AsOf>,
+    // An osmChange file (.osc) or a changeset id. Shows the area before and after it, key V
+    // switches. Created buildings are green, modified orange and deleted red
+    #[arg(long)]
+    pub change: Option<String>,
+    // Tag edits like 121486088:roof:shape=gabled, an empty value removes the tag. Shows the
+    // original and the edited, key V switches
+    #[arg(long)]
+    pub tag: Vec<TagEdit>,
+    // Upload the tag edits with this changeset comment, after the preview and a yes. Needs --api.
+    // The OAuth2 token is read from OSM_TB_TOKEN
+    #[arg(long)]
+    pub upload: Option<String>,
 }
 
 // Implement web enabled parser for your struct
@@ -46,6 +108,14 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let args: UrlClArgs = UrlClArgs::we_parse(); // Type annotations needed
     //#[cfg(debug_assertions)]
     println!("= {:?}", args);
+    // The snapshot shows one rendering, not the variants of the other modes
+    if args.snapshot.is_some()
+        && (args.compare || !args.as_of.is_empty() || args.change.is_some() || !args.tag.is_empty())
+    {
+        return Err(
+            "--snapshot does not combine with --compare, --as-of, --change or --tag".into(),
+        );
+    }
     let (element_id, element_string, is_way) = if args.relation > 0 {
         (args.relation, "relation", false)
     } else {
@@ -62,7 +132,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         element_string, element_id
     ); // Not Info! from Bevy because this sourc sould work without Bevy to. Like with rend3
 
-    let api = InputOsm::new();
+    let elevation = match &args.dem {
+        Some(directory) => Some(Arc::new(Elevation::from_directory(Path::new(directory))?)),
+        None => None,
+    };
+    let mut api = match (&args.fixtures, &args.api) {
+        (Some(directory), _) => InputOsm::from_directory(Path::new(directory)),
+        (None, Some(api_url)) => InputOsm::new().with_base_url(api_url),
+        (None, None) => InputOsm::new(),
+    }
+    .with_profile(args.profile);
+    if args.complete {
+        api = api.with_completion(COMPLETION_PASSES);
+    }
+    if let Some(directory) = &args.cache {
+        let cache = ResponseCache::new(Path::new(directory))
+            .with_refresh(args.refresh)
+            .with_offline(args.offline);
+        api = api.with_cache(cache);
+    }
+    if let Some(elevation) = &elevation {
+        api = api.with_elevation(elevation.clone());
+    }
 
     let bounding_box = api.geo_bbox_of_element(element_id, is_way).await;
     //#[cfg(debug_assertions)]
@@ -84,14 +175,243 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Loading data");
 
     let gpu_ground_null_coordinates = center_as_geographic_coordinates(&bounding_box);
-    let buildings_and_parts = api
-        .scan_osm(
-            &bounding_box,
+    let render_options = RenderOptions {
+        facade: args.facade,
+    };
+    let terrain = elevation.as_ref().map(|elevation| {
+        terrain_to_mesh(
+            elevation,
+            &gpu_ground_null_coordinates,
+            range,
+            TERRAIN_COLOR,
+        )
+    });
+
+    if args.compare {
+        let bytes = api.load_osm(&bounding_box).await?;
+        let mut variants = Vec::new();
+        for profile in ALL_PROFILES {
+            let mut input = InputOsm::new().with_profile(profile);
+            if let Some(elevation) = &elevation {
+                input = input.with_elevation(elevation.clone());
+            }
+            let buildings_and_parts = input.scan_json_to_osm_vec(
+                &bytes,
+                &gpu_ground_null_coordinates,
+                args.only,
+                element_only,
+            );
+            variants.push(MeshVariant {
+                name: profile.to_string(),
+                osm_meshes: scan_elements_from_layer_to_mesh_with_options(
+                    buildings_and_parts,
+                    &render_options,
+                ),
+            });
+        }
+        println!("Rendering ... Key V: next profile\n");
+        render_init_with_variants(
+            variants,
+            terrain,
+            gpu_ground_null_coordinates,
+            range as f32,
+            args.area == 0,
+        );
+        return Ok(());
+    }
+
+    if !args.as_of.is_empty() {
+        let history = api.load_history(element_id, is_way).await?;
+        for (version, timestamp) in history_versions(&history, element_string, element_id) {
+            println!("{element_string} {element_id} version {version}: {timestamp}");
+        }
+        let mut variants = Vec::new();
+        for as_of in &args.as_of {
+            let Some(bytes) = history_as_of(&history, as_of, element_string, element_id) else {
+                println!("No {as_of} in the history");
+                continue;
+            };
+            let buildings_and_parts = api.scan_json_to_osm_vec(
+                &bytes,
+                &gpu_ground_null_coordinates,
+                args.only,
+                element_only,
+            );
+            variants.push(MeshVariant {
+                name: as_of.to_string(),
+                osm_meshes: scan_elements_from_layer_to_mesh_with_options(
+                    buildings_and_parts,
+                    &render_options,
+                ),
+            });
+        }
+        if variants.is_empty() {
+            return Ok(());
+        }
+        println!("Rendering ... Key V: next version\n");
+        render_init_with_variants(
+            variants,
+            terrain,
+            gpu_ground_null_coordinates,
+            range as f32,
+            args.area == 0,
+        );
+        return Ok(());
+    }
+
+    if let Some(change) = &args.change {
+        let loaded = api.load_osm(&bounding_box).await?;
+        // An uploaded changeset is in the loaded data, a file of an editor is not yet
+        let (osm_change, before, after) = match change.parse::<u64>() {
+            Ok(changeset_id) => {
+                let osm_change = api.load_changeset(changeset_id).await?;
+                let previous = api.load_previous_versions(&osm_change).await?;
+                let before = osm_change.revert(&loaded, &previous);
+                (osm_change, before, Some(loaded))
+            }
+            Err(_) => {
+                let osm_change = OsmChange::from_xml(&std::fs::read_to_string(change)?)?;
+                let after = osm_change.apply(&loaded);
+                (osm_change, Some(loaded), after)
+            }
+        };
+        println!("Change: {osm_change}");
+        let (Some(before), Some(after)) = (before, after) else {
+            println!("No OSM data to apply the change on");
+            return Ok(());
+        };
+        let mut variants = Vec::new();
+        for (name, bytes) in [("before", before), ("after", after)] {
+            let mut buildings_and_parts = api.scan_json_to_osm_vec(
+                &bytes,
+                &gpu_ground_null_coordinates,
+                args.only,
+                element_only,
+            );
+            color_changes(
+                &mut buildings_and_parts,
+                &osm_change.element_actions(&bytes),
+            );
+            variants.push(MeshVariant {
+                name: name.into(),
+                osm_meshes: scan_elements_from_layer_to_mesh_with_options(
+                    buildings_and_parts,
+                    &render_options,
+                ),
+            });
+        }
+        println!("Rendering ... Key V: before or after the change\n");
+        render_init_with_variants(
+            variants,
+            terrain,
+            gpu_ground_null_coordinates,
+            range as f32,
+            args.area == 0,
+        );
+        return Ok(());
+    }
+
+    if !args.tag.is_empty() {
+        // Checked before the loading, not to find out after the preview
+        let upload = match &args.upload {
+            Some(comment) => {
+                if api.directory().is_some() || args.api.is_none() {
+                    return Err("Upload needs --api, like the dev API, and no --fixtures".into());
+                }
+                let token = std::env::var(TOKEN_VARIABLE)
+                    .map_err(|_| format!("No OAuth2 token in {TOKEN_VARIABLE}"))?;
+                Some((comment, UploadClient::new(api.api_url(), &token)))
+            }
+            None => None,
+        };
+        let bytes = api.load_osm(&bounding_box).await?;
+        let Some(mut session) = EditSession::new(&bytes) else {
+            println!("No OSM data to edit");
+            return Ok(());
+        };
+        for edit in &args.tag {
+            match session.edit(edit.clone()) {
+                Ok(()) => println!("Edit {edit}"),
+                Err(e) => println!("{e}"),
+            }
+        }
+        let original = api.scan_json_to_osm_vec(
+            &bytes,
+            &gpu_ground_null_coordinates,
+            args.only,
+            element_only,
+        );
+        let (_edited, edited_meshes) = session.preview(
+            &api,
+            &gpu_ground_null_coordinates,
+            args.only,
+            element_only,
+            &render_options,
+        );
+        let variants = vec![
+            MeshVariant {
+                name: "original".into(),
+                osm_meshes: scan_elements_from_layer_to_mesh_with_options(
+                    original,
+                    &render_options,
+                ),
+            },
+            MeshVariant {
+                name: "edited".into(),
+                osm_meshes: edited_meshes,
+            },
+        ];
+        println!("Rendering ... Key V: original or edited\n");
+        render_init_with_variants(
+            variants,
+            terrain,
+            gpu_ground_null_coordinates,
+            range as f32,
+            args.area == 0,
+        );
+
+        // Only after the preview window is closed and a yes
+        if let Some((comment, client)) = upload {
+            if session.edits().next().is_none() {
+                println!("No edits to upload");
+                return Ok(());
+            }
+            println!("Edits to upload to {}:", api.api_url());
+            for edit in session.edits() {
+                println!("  {edit}");
+            }
+            if !confirm(&format!(
+                "Upload them with the comment \"{comment}\"? [y/N] "
+            )) {
+                println!("Not uploaded");
+                return Ok(());
+            }
+            let changeset_id = client.upload(&session, comment).await?;
+            println!("Uploaded in changeset {changeset_id}");
+        }
+        return Ok(());
+    }
+
+    // In area mode, the roads, water, landuse and trees around are shown too
+    let (buildings_and_parts, ground_features) = if args.area > 0 {
+        let bytes = api.load_osm(&bounding_box).await?;
+        api.scan_json_to_layers_vec(
+            &bytes,
             &gpu_ground_null_coordinates,
             args.only,
             element_only,
         )
-        .await?;
+    } else {
+        let buildings_and_parts = api
+            .scan_osm(
+                &bounding_box,
+                &gpu_ground_null_coordinates,
+                args.only,
+                element_only,
+            )
+            .await?;
+        (buildings_and_parts, Vec::new())
+    };
     // println!("buildings_and_parts: {:?}", buildings_and_parts);
 
     if buildings_and_parts.is_empty() {
@@ -99,13 +419,64 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         return Ok(());
     }
 
+    if let Some(svg_file) = &args.svg {
+        std::fs::write(
+            svg_file,
+            scan_elements_from_layer_to_svg(&buildings_and_parts),
+        )?;
+        println!("Plan view written: {svg_file}");
+    }
+
+    let mut meshes =
+        scan_elements_from_layer_to_mesh_with_options(buildings_and_parts, &render_options);
+    if !ground_features.is_empty() {
+        let mut ground = ground_to_mesh(&ground_features);
+        if let Some(elevation) = &elevation {
+            lift_onto_terrain(&mut ground, elevation, &gpu_ground_null_coordinates);
+        }
+        meshes.push(ground);
+    }
+
+    if args.check {
+        let problems = check_meshes(&meshes);
+        println!(
+            "Mesh check: {} problems {}",
+            problems.len(),
+            mesh_problems_summary(&problems)
+        );
+        for problem in &problems {
+            println!("{problem}");
+        }
+    }
+
+    if let Some(snapshot_file) = &args.snapshot {
+        meshes.extend(terrain);
+        let camera = SnapshotCamera {
+            distance: range as f32 * 1.5,
+            ..Default::default()
+        };
+        render_snapshot(&meshes, &camera).write_png(Path::new(snapshot_file))?;
+        println!("Snapshot written: {snapshot_file}");
+        return Ok(());
+    }
+
     println!("Rendering ...\n");
-    let meshes = scan_elements_from_layer_to_mesh(buildings_and_parts);
     render_init(
         meshes,
+        terrain,
+        gpu_ground_null_coordinates,
         range as f32,
         args.area == 0, /* area not used? use first mouse key for orientation */
     );
 
     Ok(())
 }
+
+// Asks on the terminal. Only y or yes is a yes
+fn confirm(question: &str) -> bool {
+    print!("{question}");
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
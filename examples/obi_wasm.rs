@@ -3,8 +3,7 @@
 
 // Usefull info for (Custom-) asset: https://taintedcoders.com/bevy/assets
 
-const LOCAL_TEST: bool = false;
-// Test with native build and local files runs well. Not with web files. See C) below
+// Test with native build and local files (--fixtures) runs well. Not with web files. See C) below
 // Test with wasm build and local files runs well.
 
 use osm_tb::*;
@@ -143,6 +142,12 @@ pub struct UrlCommandLineArgs {
     pub only: i32,
     #[arg(short, long, default_value = "0")]
     pub area: i32,
+    // Another API, like https://master.apis.dev.openstreetmap.org/api/0.6/
+    #[arg(long, default_value = "")]
+    pub api: String,
+    // Fixtures in the assets directory: way.json or relation.json and bbox.json. Like: --fixtures .
+    #[arg(long, default_value = "")]
+    pub fixtures: String,
 }
 // How to run:
 // RUST_BACKTRACE=1 cargo run --example obi_wasm -- --way 139890029  // Error! in bevy_web_asset (html-lib)
@@ -161,6 +166,12 @@ fn read_and_use_args(
         (args.way, true)
     };
 
+    if !args.fixtures.is_empty() {
+        state.api = osm_tb::InputOsm::from_directory(std::path::Path::new(&args.fixtures));
+    } else if !args.api.is_empty() {
+        state.api = osm_tb::InputOsm::new().with_base_url(&args.api);
+    }
+
     state.element_id = element_id;
     state.is_way = is_way;
     state.show_only = args.only as u64;
@@ -187,6 +198,8 @@ struct AppState {
     gpu_ground_null_coordinates: osm_tb::GeographicCoordinates,
 }
 
+// A Bevy system takes what it needs as arguments
+#[allow(clippy::too_many_arguments)]
 fn on_load(
     commands: Commands,
     meshes: ResMut<Assets<Mesh>>,
@@ -228,20 +241,16 @@ fn on_load(
             app_state.element_id,
             app_state.is_way,
         );
-        max_range(&mut bounding_box, app_state.range as f64);
+        max_range(&mut bounding_box, app_state.range);
         app_state.range = bounding_box.width().max(bounding_box.height());
         //p_state.range = (bounding_box.max_radius() * osm_tb::LAT_FAKT as FGP) as f32;
         control_value.distance = app_state.range as f32; // * 1.0
 
         // load building
         app_state.gpu_ground_null_coordinates = center_as_geographic_coordinates(&bounding_box);
-        let mut url = app_state.api.bbox_url(&bounding_box);
+        let url = app_state.api.bbox_url(&bounding_box);
         info!("**** bbox_url: {url}");
 
-        if LOCAL_TEST {
-            url = "bbox.json".into();
-        }
-
         app_state.asset = asset_server.load(&url);
         app_state.step1 = true;
 
@@ -315,13 +324,9 @@ fn setup(mut commands: Commands, mut state: ResMut<AppState>, asset_server: Res<
         },
     ));
 
-    let mut url = state.api.element_url(state.element_id, state.is_way);
+    let url = state.api.element_url(state.element_id, state.is_way);
     info!("= Way_URL: {url}");
 
-    if LOCAL_TEST {
-        url = "way.json".into();
-    }
-
     state.asset = asset_server.load(url);
 }
 
@@ -329,7 +334,7 @@ fn main() {
     // Outputs (info! println!) don't work in this main fn
 
     App::new()
-        .add_plugins(WebAssetPlugin::default()) // for http(s)
+        .add_plugins(WebAssetPlugin) // for http(s)
         .add_plugins(
             DefaultPlugins
                 // It seems like the macOS Safari does not run fullscreen with the old code in the old dir, but iPad doas! Hm?
@@ -352,6 +357,7 @@ fn main() {
         .init_asset_loader::<OsmApiAssetLoader>()
         .add_systems(Startup, setup)
         .add_plugins(osm_tb::ControlWithCamera)
+        .add_plugins(osm_tb::ViewerToggles)
         .add_systems(Update, on_load)
         //.init_resource::<WatchDogTime>()
         //.add_systems(Update, (watch_dog, end_watch_dog.after(watch_dog)))
@@ -0,0 +1,43 @@
+// Outlines of any shape through the Footprint operations, used by the roofs and for parts
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use osm_tb::{Footprint, GroundPosition};
+
+#[derive(Arbitrary, Debug)]
+struct Input {
+    outer: Vec<(i16, i16)>, // decimeters, like the nodes of a way
+    hole: Vec<(i16, i16)>,
+    angle: u16, // tenth of a degree
+}
+
+fn footprint(corners: &[(i16, i16)]) -> Footprint {
+    let mut footprint = Footprint::new();
+    for (x, y) in corners {
+        footprint.push_position(GroundPosition {
+            x: *x as f64 / 10.,
+            y: *y as f64 / 10.,
+        });
+    }
+    footprint.close();
+    footprint
+}
+
+fuzz_target!(|input: Input| {
+    let angle = (input.angle as f64 / 10.).to_radians();
+    let mut outer = footprint(&input.outer);
+    let hole = footprint(&input.hole);
+
+    let _ = outer.rotate(angle);
+    let _ = outer.get_area_size();
+    let _ = outer.other_is_inside(&hole);
+    let (min, max) = outer.extent_along(angle);
+    let _ = outer.slice_along(angle, min, (min + max) / 2.);
+    let _ = outer.snap_to_edge_normal(angle, 0.1);
+    outer.subtract(&hole);
+    for polygon_index in 0..outer.multipolygon.0.len() {
+        let _ = outer.get_triangulates(polygon_index);
+    }
+    let _ = outer.rotate(angle);
+});
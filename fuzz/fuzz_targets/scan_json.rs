@@ -0,0 +1,20 @@
+// Any bytes as an OSM API answer: read, convert to buildings and parts and render them
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use osm_tb::{
+    GeographicCoordinates, scan_elements_from_layer_to_mesh, scan_elements_from_layer_to_svg,
+    scan_json_bytes_to_osm,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // Near the nodes of the fixtures in assets/
+    let origin = GeographicCoordinates {
+        latitude: 49.7559,
+        longitude: 11.1356,
+    };
+    let buildings_and_parts =
+        scan_json_bytes_to_osm(bytes::Bytes::copy_from_slice(data), &origin, 0, 0);
+    let _ = scan_elements_from_layer_to_svg(&buildings_and_parts);
+    let _ = scan_elements_from_layer_to_mesh(buildings_and_parts);
+});
@@ -122,6 +122,9 @@ pub struct KeyBindings {
     pub zoom_out2: KeyCode,
     //
     pub reset: KeyCode,
+    pub toggle_ground: KeyCode,
+    pub next_variant: KeyCode,
+    pub editor_links: KeyCode,
 }
 
 impl Default for KeyBindings {
@@ -150,6 +153,9 @@ impl Default for KeyBindings {
             zoom_out2: KeyCode::KeyY, // Z on German keyboards
             //
             reset: KeyCode::Digit0,
+            toggle_ground: KeyCode::KeyU, // Underground
+            next_variant: KeyCode::KeyV,
+            editor_links: KeyCode::KeyL,
         }
     }
 }
@@ -1,6 +1,9 @@
-use crate::bevy_control::{ControlValues, ControlWithCamera};
-use crate::kernel_in::PI;
+use crate::bevy_control::{ControlValues, ControlWithCamera, KeyBindings};
+use std::f32::consts::PI;
+
+use crate::kernel_in::GeographicCoordinates;
 use crate::kernel_out::OsmMeshAttributes;
+use crate::symbolic_3d::from_gpu_position;
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 // BEVY ///////////////////////////////////////////////////////////////////////////////////////////
@@ -12,16 +15,39 @@ use bevy::render::{
     render_asset::RenderAssetUsages,
     render_resource::PrimitiveTopology,
 };
+use bevy::window::PrimaryWindow;
 
 #[derive(Resource)]
 struct StartingValues {
-    pub osm_meshes: Vec<OsmMeshAttributes>,
+    pub variants: Vec<MeshVariant>,
     pub range: f32,
+    pub terrain: Option<OsmMeshAttributes>,
     //pub ui: Option<EntityCommands>,
 }
 
+// One set of meshes to show, like the buildings read by one interpretation profile.
+// The viewer shows one variant at a time and switches by a key, to compare them
+pub struct MeshVariant {
+    pub name: String,
+    pub osm_meshes: Vec<OsmMeshAttributes>,
+}
+
+#[derive(Component)]
+struct Variant(usize);
+
+// The lat/lon of the GPU position 0,0,0. Needed to show the lat/lon at the cursor
+#[derive(Resource)]
+struct GroundOrigin(GeographicCoordinates);
+
+#[derive(Resource)]
+struct Variants {
+    names: Vec<String>,
+    shown: usize,
+}
+
 fn spawn_osm_mesh(
     osm_mesh: &OsmMeshAttributes,
+    variant: usize,
     commands: &mut Commands,
     meshes: &mut ResMut<Assets<Mesh>>,
     materials: &mut ResMut<Assets<StandardMaterial>>,
@@ -44,6 +70,12 @@ fn spawn_osm_mesh(
     commands.spawn((
         Mesh3d(meshes.add(mesh)),
         MeshMaterial3d(materials.add(Color::srgb(1., 1., 1.))),
+        Variant(variant),
+        if variant == 0 {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        },
     ));
 }
 
@@ -54,21 +86,48 @@ fn setup(
     mut materials: ResMut<Assets<StandardMaterial>>,
     starting_values: ResMut<StartingValues>,
 ) {
-    for mesh in &starting_values.osm_meshes {
-        spawn_osm_mesh(mesh, &mut commands, &mut meshes, &mut materials);
+    for (index, variant) in starting_values.variants.iter().enumerate() {
+        for mesh in &variant.osm_meshes {
+            spawn_osm_mesh(mesh, index, &mut commands, &mut meshes, &mut materials);
+        }
     }
 
-    environment(commands, meshes, materials, starting_values.range);
+    commands.spawn((
+        Text::new(""),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(5.),
+            left: Val::Px(5.),
+            ..default()
+        },
+        TextUI,
+    ));
+
+    environment(
+        commands,
+        meshes,
+        materials,
+        starting_values.range,
+        starting_values.terrain.as_ref(),
+    );
 }
 
+// The lat/lon at the cursor
 #[derive(Component)]
 struct TextUI;
 
+// The ground plane. It may get semi-transparent to see underground parts
+#[derive(Component)]
+pub struct Ground;
+
+const GROUND_ALPHA_TRANSPARENT: f32 = 0.35;
+
 fn environment(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     range: f32,
+    terrain: Option<&OsmMeshAttributes>,
     // starting_values: &mut StartingValues,
 ) {
     //let range = starting_values.range;
@@ -97,25 +156,232 @@ fn environment(
     // circular base
     const SLIGHTLY_BELOW_GROUND_0: f32 = -0.01;
 
+    // The terrain, if there is elevation data. The vertex colours are green
+    if let Some(terrain) = terrain {
+        let mut mesh = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+        )
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, terrain.vertices_positions.clone())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, terrain.vertices_colors.clone())
+        .with_inserted_indices(Indices::U32(terrain.indices_to_vertices.clone()));
+        mesh.compute_normals();
+        commands.spawn((
+            Mesh3d(meshes.add(mesh)),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                double_sided: true,
+                cull_mode: None,
+                ..default()
+            })),
+            Transform::from_xyz(0., SLIGHTLY_BELOW_GROUND_0, 0.),
+            Ground,
+        ));
+        return;
+    }
+
     commands.spawn((
         Mesh3d(meshes.add(Rectangle::new(range * 2.0, range * 2.0))),
-        MeshMaterial3d(materials.add(Color::srgb_u8(150, 255, 150))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgb_u8(150, 255, 150),
+            // Seen from below, if semi-transparent
+            double_sided: true,
+            cull_mode: None,
+            ..default()
+        })),
         Transform {
             translation: Vec3::new(0., SLIGHTLY_BELOW_GROUND_0, 0.),
             rotation: Quat::from_rotation_x(-std::f32::consts::FRAC_PI_2),
             ..default()
         },
+        Ground,
     ));
 }
 
+// Key U: Switch the ground between opaque and semi-transparent, to see the volumes below it
+fn toggle_ground(
+    keys: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    ground: Query<&MeshMaterial3d<StandardMaterial>, With<Ground>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !keys.just_pressed(key_bindings.toggle_ground) {
+        return;
+    }
+    for material_handle in &ground {
+        if let Some(material) = materials.get_mut(&material_handle.0) {
+            if material.base_color.alpha() < 1.0 {
+                material.base_color.set_alpha(1.0);
+                material.alpha_mode = AlphaMode::Opaque;
+            } else {
+                material.base_color.set_alpha(GROUND_ALPHA_TRANSPARENT);
+                material.alpha_mode = AlphaMode::Blend;
+            }
+        }
+    }
+}
+
+// Key V: Show the next mesh variant, like the next interpretation profile
+fn next_variant(
+    keys: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    variants: Option<ResMut<Variants>>,
+    mut query: Query<(&Variant, &mut Visibility)>,
+) {
+    let Some(mut variants) = variants else {
+        return;
+    };
+    if !keys.just_pressed(key_bindings.next_variant) || variants.names.len() < 2 {
+        return;
+    }
+    variants.shown = (variants.shown + 1) % variants.names.len();
+    println!("Showing: {}", variants.names[variants.shown]);
+    for (variant, mut visibility) in &mut query {
+        *visibility = if variant.0 == variants.shown {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
+
+// The distance along the ray to the closest terrain triangle it hits, seen from both sides
+fn terrain_hit(ray: Ray3d, terrain: &OsmMeshAttributes) -> Option<f32> {
+    let origin = ray.origin;
+    let direction = *ray.direction;
+    let mut closest: Option<f32> = None;
+    for triangle in terrain.indices_to_vertices.chunks_exact(3) {
+        let corner = |index: usize| {
+            terrain
+                .vertices_positions
+                .get(triangle[index] as usize)
+                .map(|position| Vec3::from_array(*position))
+        };
+        let (Some(a), Some(b), Some(c)) = (corner(0), corner(1), corner(2)) else {
+            continue;
+        };
+        // Möller–Trumbore
+        let (edge_ab, edge_ac) = (b - a, c - a);
+        let across = direction.cross(edge_ac);
+        let determinant = edge_ab.dot(across);
+        if determinant.abs() < f32::EPSILON {
+            continue; // parallel
+        }
+        let from_a = origin - a;
+        let u = from_a.dot(across) / determinant;
+        let up = from_a.cross(edge_ab);
+        let v = direction.dot(up) / determinant;
+        if u < 0. || v < 0. || u + v > 1. {
+            continue;
+        }
+        let distance = edge_ac.dot(up) / determinant;
+        if distance > 0. && closest.is_none_or(|closest| distance < closest) {
+            closest = Some(distance);
+        }
+    }
+    closest
+}
+
+/*
+ * Shows the lat/lon of the ground under the cursor.
+ * Key L: prints links to open the iD or JOSM editor there, to fix what is seen
+ */
+fn cursor_coordinates(
+    keys: Res<ButtonInput<KeyCode>>,
+    key_bindings: Res<KeyBindings>,
+    origin: Option<Res<GroundOrigin>>,
+    starting_values: Option<Res<StartingValues>>,
+    primary_window: Query<&Window, With<PrimaryWindow>>,
+    camera: Query<(&Camera, &GlobalTransform)>,
+    mut text_query: Query<&mut Text, With<TextUI>>,
+) {
+    let Some(origin) = origin else {
+        return;
+    };
+    let (Ok(window), Ok((camera, camera_transform))) = (primary_window.single(), camera.single())
+    else {
+        return;
+    };
+    let Some(cursor) = window.cursor_position() else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    // The terrain, or without elevation data the ground plane at height 0.
+    // Looking above the horizon, there is no ground
+    let terrain = starting_values
+        .as_ref()
+        .and_then(|starting_values| starting_values.terrain.as_ref());
+    let distance = match terrain {
+        Some(terrain) => terrain_hit(ray, terrain),
+        None => ray.intersect_plane(Vec3::ZERO, InfinitePlane3d::new(Vec3::Y)),
+    };
+    let Some(distance) = distance else {
+        return;
+    };
+    let point = ray.get_point(distance);
+    let (ground_position, _height) = from_gpu_position(&point.to_array());
+    let coordinates = origin.0.position_to_coordinates(ground_position);
+
+    for mut text in &mut text_query {
+        text.0 = format!(
+            "{:.6} {:.6}   Key L: editor links",
+            coordinates.latitude, coordinates.longitude
+        );
+    }
+    if keys.just_pressed(key_bindings.editor_links) {
+        println!("iD:   {}", coordinates.id_editor_url());
+        println!("JOSM: {}", coordinates.josm_url());
+    }
+}
+
+/// Viewer switches by keys, like the semi-transparent ground
+pub struct ViewerToggles;
+impl Plugin for ViewerToggles {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_ground, next_variant, cursor_coordinates));
+    }
+}
+
 // BEVY-APP ///////////////
 // examples like obi.rs have no Bevy code. They init Bevy here:
 pub fn render_init(
     osm_meshes: Vec<OsmMeshAttributes>,
+    terrain: Option<OsmMeshAttributes>,
+    gpu_ground_null_coordinates: GeographicCoordinates,
+    range: f32,
+    use_first_mouse_key_for_orientation: bool,
+) {
+    let variants = vec![MeshVariant {
+        name: "".into(),
+        osm_meshes,
+    }];
+    render_init_with_variants(
+        variants,
+        terrain,
+        gpu_ground_null_coordinates,
+        range,
+        use_first_mouse_key_for_orientation,
+    );
+}
+
+// Like render_init, with more variants of the meshes to switch between
+pub fn render_init_with_variants(
+    variants: Vec<MeshVariant>,
+    terrain: Option<OsmMeshAttributes>,
+    gpu_ground_null_coordinates: GeographicCoordinates,
     range: f32,
     use_first_mouse_key_for_orientation: bool,
 ) {
-    let starting_values = StartingValues { osm_meshes, range };
+    let names = variants
+        .iter()
+        .map(|variant| variant.name.clone())
+        .collect();
+    let starting_values = StartingValues {
+        variants,
+        range,
+        terrain,
+    };
 
     let control_values = ControlValues {
         use_first_point_for_rotation: use_first_mouse_key_for_orientation,
@@ -138,9 +404,12 @@ pub fn render_init(
     )
     .insert_resource(ClearColor(Color::srgb(0.5, 0.5, 1.0)))
     .insert_resource(starting_values)
+    .insert_resource(Variants { names, shown: 0 })
+    .insert_resource(GroundOrigin(gpu_ground_null_coordinates))
     .add_systems(Startup, setup)
     .insert_resource(control_values)
     .add_plugins(ControlWithCamera)
+    .add_plugins(ViewerToggles)
     .run();
 }
 
@@ -156,9 +425,9 @@ pub fn bevy_osm_load(
 ) {
     // OSM meshes
     for mesh in &osm_meshes {
-        spawn_osm_mesh(mesh, &mut commands, &mut meshes, &mut materials);
+        spawn_osm_mesh(mesh, 0, &mut commands, &mut meshes, &mut materials);
     }
     commands.insert_resource(ClearColor(Color::srgb(0.5, 0.5, 1.0)));
 
-    environment(commands, meshes, materials, range);
+    environment(commands, meshes, materials, range, None);
 }
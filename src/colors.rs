@@ -0,0 +1,88 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Colours and materials of buildings and roofs ///////////////////////////////////////////////////
+
+use csscolorparser::parse;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::kernel_in::RenderColor;
+
+// The palette of the crate. Another one may be loaded by Palette::from_json
+static DEFAULT_PALETTE: &str = include_str!("palette.json");
+
+/*
+ * Colour names and materials, not known by CSS, as "#rrggbb" values.
+ * A light_ or dark_ prefix multiplies the RGB values by the light or dark factor.
+ */
+#[derive(Clone, Debug, Deserialize)]
+pub struct Palette {
+    pub light: f32,
+    pub dark: f32,
+    pub colors: HashMap<String, String>,
+    pub materials: HashMap<String, String>,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self::from_json(DEFAULT_PALETTE).expect("palette.json of the crate is valid")
+    }
+}
+
+impl Palette {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /*
+     * A colour or material value of the tags colour, building:colour, building:material etc.
+     * "Light Grey", "light-grey", "lightgrey" and "light_grey" are all the same.
+     * @return None if the value is unknown
+     */
+    pub fn parse_color(&self, value: &str) -> Option<RenderColor> {
+        let name = value.trim().to_lowercase().replace([' ', '-'], "_");
+        if let Some(color) = self.parse_name(&name) {
+            return Some(color);
+        }
+
+        let (rest, factor) = if let Some(rest) = name.strip_prefix("light") {
+            (rest, self.light)
+        } else if let Some(rest) = name.strip_prefix("dark") {
+            (rest, self.dark)
+        } else {
+            return None;
+        };
+        let rest = rest.strip_prefix('_').unwrap_or(rest);
+        let mut color = self.parse_name(rest)?;
+        for channel in color.iter_mut().take(3) {
+            *channel = (*channel * factor).min(1.);
+        }
+        Some(color)
+    }
+
+    fn parse_name(&self, name: &str) -> Option<RenderColor> {
+        if let Some(hex) = self.colors.get(name) {
+            return to_render_color(hex);
+        }
+        // https://docs.rs/csscolorparser/latest/csscolorparser/  CSS names have no underscores
+        if let Some(color) =
+            to_render_color(name).or_else(|| to_render_color(&name.replace('_', "")))
+        {
+            return Some(color);
+        }
+        if let Some(hex) = self.materials.get(name) {
+            return to_render_color(hex);
+        }
+        None
+    }
+}
+
+// Bevy pbr color needs f32, The parse has no .as_f32
+fn to_render_color(value: &str) -> Option<RenderColor> {
+    let color = parse(value).ok()?;
+    Some([
+        color.r as f32,
+        color.g as f32,
+        color.b as f32,
+        (color.a as f32).clamp(0., 1.),
+    ])
+}
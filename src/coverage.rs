@@ -0,0 +1,143 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Coverage of a building by its parts: which parts belong to it and what of the outline is left //
+
+use geo::{
+    Area, BooleanOps, BoundingRect, Contains, Distance, Euclidean, Length, MultiPolygon, Point,
+    Polygon,
+};
+
+// Part areas, inside the building by this share, are inside. Below are precision errors of the nodes
+static INSIDE_SHARE: f64 = 0.99;
+static WIDTH_SAMPLES: usize = 20; // per side of the bounding box
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CoverageRules {
+    pub min_area: f64,  // square meters. Smaller remains of a building are dropped
+    pub min_width: f64, // meters. Thinner remains are dropped, like "infinite lines" along the parts
+    // An overlapping part belongs to the building, if this share of the part is inside
+    pub overlap_min_share: f64,
+}
+
+impl Default for CoverageRules {
+    fn default() -> Self {
+        Self {
+            min_area: 1.0,
+            min_width: 0.5,
+            overlap_min_share: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PartCoverage {
+    #[default]
+    Inside,
+    // part_share: of the part inside the building, building_share: of the building covered by the part
+    Overlapping {
+        part_share: f64,
+        building_share: f64,
+    },
+    Outside,
+}
+
+impl PartCoverage {
+    // Inside, mostly inside or covering the whole building
+    pub fn belongs_to_building(&self, rules: &CoverageRules) -> bool {
+        match self {
+            PartCoverage::Inside => true,
+            PartCoverage::Overlapping {
+                part_share,
+                building_share,
+            } => *part_share >= rules.overlap_min_share || *building_share >= INSIDE_SHARE,
+            PartCoverage::Outside => false,
+        }
+    }
+
+    // The share of the part area outside of the building
+    pub fn outside_share(&self) -> f64 {
+        match self {
+            PartCoverage::Inside => 0.,
+            PartCoverage::Overlapping { part_share, .. } => 1. - part_share,
+            PartCoverage::Outside => 1.,
+        }
+    }
+}
+
+pub fn classify_part(building: &MultiPolygon, part: &MultiPolygon) -> PartCoverage {
+    let part_area = part.unsigned_area();
+    let building_area = building.unsigned_area();
+    if part_area == 0. || building_area == 0. {
+        return PartCoverage::Outside;
+    }
+    let common_area = building.intersection(part).unsigned_area();
+    let part_share = common_area / part_area;
+    if part_share >= INSIDE_SHARE {
+        PartCoverage::Inside
+    } else if common_area > 0. {
+        PartCoverage::Overlapping {
+            part_share,
+            building_share: common_area / building_area,
+        }
+    } else {
+        PartCoverage::Outside
+    }
+}
+
+// Drop the polygons of the remaining outline, which are too small or too thin to be a building
+pub fn remove_slivers(multipolygon: &MultiPolygon, rules: &CoverageRules) -> MultiPolygon {
+    MultiPolygon::new(
+        multipolygon
+            .iter()
+            .filter(|polygon| {
+                polygon.unsigned_area() >= rules.min_area
+                    && polygon_width(polygon) >= rules.min_width
+            })
+            .cloned()
+            .collect(),
+    )
+}
+
+/*
+ * Twice the largest distance of an inside point to the edges. Like a negative buffer:
+ * A polygon thinner than the width vanishes, if it is shrunk by half the width.
+ * The inside points are sampled on a grid, so the width may be a bit too small.
+ * A thin diagonal polygon may have no grid point inside or only close to an edge,
+ * so it is at least twice the area by the perimeter, about the width of a long strip
+ */
+pub fn polygon_width(polygon: &Polygon) -> f64 {
+    let Some(bounding_box) = polygon.bounding_rect() else {
+        return 0.;
+    };
+    let step_x = bounding_box.width() / WIDTH_SAMPLES as f64;
+    let step_y = bounding_box.height() / WIDTH_SAMPLES as f64;
+    let mut largest: f64 = 0.;
+    for x in 0..WIDTH_SAMPLES {
+        for y in 0..WIDTH_SAMPLES {
+            let point = Point::new(
+                bounding_box.min().x + (x as f64 + 0.5) * step_x,
+                bounding_box.min().y + (y as f64 + 0.5) * step_y,
+            );
+            if !polygon.contains(&point) {
+                continue;
+            }
+            let mut distance = Euclidean.distance(&point, polygon.exterior());
+            for hole in polygon.interiors() {
+                distance = distance.min(Euclidean.distance(&point, hole));
+            }
+            largest = largest.max(distance);
+        }
+    }
+    // A lower bound of the width of convex polygons, for a thin strip about the width
+    let perimeter = Euclidean.length(polygon.exterior())
+        + polygon
+            .interiors()
+            .iter()
+            .map(|hole| Euclidean.length(hole))
+            .sum::<f64>();
+    let by_area = if perimeter > 0. {
+        2. * polygon.unsigned_area() / perimeter
+    } else {
+        0.
+    };
+    (largest * 2.).max(by_area)
+}
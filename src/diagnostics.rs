@@ -0,0 +1,44 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Diagnostics: tagging problems found while converting OSM data, instead of printing them ////////
+
+use crate::length::LengthError;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiagnosticKind {
+    UnknownColor, // neither a colour nor a material of the palette
+    BadLength(LengthError),
+    HeightConflict, // heights and levels do not fit together
+    Incomplete,     // nodes or members are not in the loaded data
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub id: u64, // OSM id of the way or relation
+    pub kind: DiagnosticKind,
+    pub value: String, // the tag value, causing the problem, or a description of the conflict
+}
+
+pub type Diagnostics = Vec<Diagnostic>;
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            DiagnosticKind::UnknownColor => {
+                write!(f, "OSM id {}: Bad color: {}", self.id, self.value)
+            }
+            DiagnosticKind::BadLength(error) => {
+                write!(
+                    f,
+                    "OSM id {}: Bad length ({error}): {}",
+                    self.id, self.value
+                )
+            }
+            DiagnosticKind::HeightConflict => {
+                write!(f, "OSM id {}: Height conflict: {}", self.id, self.value)
+            }
+            DiagnosticKind::Incomplete => {
+                write!(f, "OSM id {}: Incomplete: {}", self.id, self.value)
+            }
+        }
+    }
+}
@@ -0,0 +1,310 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Editing: tag edits on the loaded data, previewed in 3D and uploaded as a changeset ////////////
+
+/*
+ * An EditSession keeps the loaded OSM JSON and the tag edits on it. Each preview scans the
+ * edited data again, by Osm2Layer and the mesher, so an edit is seen at once.
+ * Only tags are edited, no geometry. The edited elements become an osmChange document.
+ *
+ * Upload: https://wiki.openstreetmap.org/wiki/API_v0.6#Changesets_2
+ * The client opens a changeset, uploads the osmChange and closes the changeset.
+ * It needs an OAuth2 access token with the scope write_api, see
+ * https://wiki.openstreetmap.org/wiki/OAuth#OAuth_2.0_2
+ * Try it with the dev API first: https://master.apis.dev.openstreetmap.org/api/0.6/
+ */
+
+use std::str::FromStr;
+
+use bytes::Bytes;
+use serde_json::{Value, json};
+
+use crate::input_osm_json::InputOsm;
+use crate::kernel_in::{BuildingsAndParts, GeographicCoordinates};
+use crate::kernel_out::OsmMeshAttributes;
+use crate::osm_change::escape;
+use crate::symbolic_3d::{RenderOptions, scan_elements_from_layer_to_mesh_with_options};
+
+// One tag of a way or relation, like a BuildingOrPart id. No value: the tag is removed
+#[derive(Clone, Debug, PartialEq)]
+pub struct TagEdit {
+    pub id: u64,
+    pub key: String,
+    pub value: Option<String>,
+}
+
+// Like 121486088:roof:shape=gabled, an empty value removes the tag: 121486088:height=
+impl FromStr for TagEdit {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let usage = || format!("Tag edit {text} is not like: 121486088:roof:shape=gabled");
+        let (id, tag) = text.split_once(':').ok_or_else(usage)?;
+        let (key, value) = tag.split_once('=').ok_or_else(usage)?;
+        let id = id.parse().map_err(|_| usage())?;
+        if key.is_empty() {
+            return Err(usage());
+        }
+        Ok(Self {
+            id,
+            key: key.into(),
+            value: (!value.is_empty()).then(|| value.into()),
+        })
+    }
+}
+
+impl std::fmt::Display for TagEdit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            Some(value) => write!(f, "{}: {}={}", self.id, self.key, value),
+            None => write!(f, "{}: {} removed", self.id, self.key),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EditSession {
+    original: Value,
+    // with the element type: way or relation
+    edits: Vec<(String, TagEdit)>,
+}
+
+impl EditSession {
+    // None if the bytes are no OSM JSON
+    pub fn new(osm_json: &[u8]) -> Option<Self> {
+        let original: Value = serde_json::from_slice(osm_json).ok()?;
+        original["elements"].as_array()?;
+        Some(Self {
+            original,
+            edits: Vec::new(),
+        })
+    }
+
+    pub fn edits(&self) -> impl Iterator<Item = &TagEdit> {
+        self.edits.iter().map(|(_, edit)| edit)
+    }
+
+    // A later edit of the same tag replaces the earlier one
+    pub fn edit(&mut self, edit: TagEdit) -> Result<(), String> {
+        // A way and a relation may have the same id. Buildings are mostly ways
+        let element_type = ["way", "relation"]
+            .into_iter()
+            .find(|element_type| self.find(element_type, edit.id).is_some())
+            .ok_or(format!("No way or relation {} in the loaded data", edit.id))?;
+        self.edits
+            .retain(|(_, other)| other.id != edit.id || other.key != edit.key);
+        self.edits.push((element_type.into(), edit));
+        Ok(())
+    }
+
+    pub fn undo(&mut self) -> Option<TagEdit> {
+        self.edits.pop().map(|(_, edit)| edit)
+    }
+
+    // The loaded OSM JSON with the edits, to scan it like loaded data
+    pub fn edited_json(&self) -> Bytes {
+        let mut edited = self.original.clone();
+        if let Some(elements) = edited["elements"].as_array_mut() {
+            for element in elements {
+                self.apply_edits(element);
+            }
+        }
+        Bytes::from(serde_json::to_vec(&edited).unwrap_or_default())
+    }
+
+    // The edited buildings and parts and their meshes, with the options of the input
+    pub fn preview(
+        &self,
+        input: &InputOsm,
+        gpu_ground_null_coordinates: &GeographicCoordinates,
+        show_only: u64,
+        way_only: u64,
+        render_options: &RenderOptions,
+    ) -> (BuildingsAndParts, Vec<OsmMeshAttributes>) {
+        let buildings_and_parts = input.scan_json_to_osm_vec(
+            &self.edited_json(),
+            gpu_ground_null_coordinates,
+            show_only,
+            way_only,
+        );
+        let osm_meshes = scan_elements_from_layer_to_mesh_with_options(
+            buildings_and_parts.clone(),
+            render_options,
+        );
+        (buildings_and_parts, osm_meshes)
+    }
+
+    /*
+     * The edited elements as osmChange, all in <modify>, in the order of their first edit.
+     * Each one complete, with nodes or members and all tags, like the API needs it.
+     * Err if an element has no version, then the API would not take it
+     */
+    pub fn osm_change(&self, changeset_id: u64) -> Result<String, String> {
+        let mut elements: Vec<(&str, u64)> = Vec::new();
+        for (element_type, edit) in &self.edits {
+            if !elements.contains(&(element_type, edit.id)) {
+                elements.push((element_type, edit.id));
+            }
+        }
+
+        let mut xml = String::new();
+        xml.push_str("<osmChange version=\"0.6\" generator=\"osm_tb\">\n<modify>\n");
+        for (element_type, id) in elements {
+            let Some(original) = self.find(element_type, id) else {
+                continue;
+            };
+            let mut element = original.clone();
+            self.apply_edits(&mut element);
+            let version = element["version"]
+                .as_u64()
+                .ok_or(format!("{element_type} {id} has no version"))?;
+            xml.push_str(&format!(
+                "  <{element_type} id=\"{id}\" version=\"{version}\" changeset=\"{changeset_id}\">\n"
+            ));
+            for node in element["nodes"].as_array().into_iter().flatten() {
+                xml.push_str(&format!("    <nd ref=\"{node}\"/>\n"));
+            }
+            for member in element["members"].as_array().into_iter().flatten() {
+                xml.push_str(&format!(
+                    "    <member type=\"{}\" ref=\"{}\" role=\"{}\"/>\n",
+                    escape(member["type"].as_str().unwrap_or_default()),
+                    member["ref"],
+                    escape(member["role"].as_str().unwrap_or_default())
+                ));
+            }
+            for (key, value) in element["tags"].as_object().into_iter().flatten() {
+                xml.push_str(&format!(
+                    "    <tag k=\"{}\" v=\"{}\"/>\n",
+                    escape(key),
+                    escape(value.as_str().unwrap_or_default())
+                ));
+            }
+            xml.push_str(&format!("  </{element_type}>\n"));
+        }
+        xml.push_str("</modify>\n</osmChange>\n");
+        Ok(xml)
+    }
+
+    fn find(&self, element_type: &str, id: u64) -> Option<&Value> {
+        self.original["elements"]
+            .as_array()?
+            .iter()
+            .find(|element| {
+                element["type"].as_str() == Some(element_type) && element["id"].as_u64() == Some(id)
+            })
+    }
+
+    fn apply_edits(&self, element: &mut Value) {
+        for (element_type, edit) in &self.edits {
+            if element["type"].as_str() != Some(element_type)
+                || element["id"].as_u64() != Some(edit.id)
+            {
+                continue;
+            }
+            if !element["tags"].is_object() {
+                element["tags"] = json!({});
+            }
+            let Some(tags) = element["tags"].as_object_mut() else {
+                continue;
+            };
+            match &edit.value {
+                Some(value) => tags.insert(edit.key.clone(), value.as_str().into()),
+                None => tags.remove(&edit.key),
+            };
+        }
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Upload ////////////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug)]
+pub struct UploadClient {
+    api_url: String,
+    token: String, // OAuth2 access token
+}
+
+impl UploadClient {
+    // The API URL like InputOsm::with_base_url, ending with the version: /api/0.6/
+    pub fn new(api_url: &str, token: &str) -> Self {
+        let mut api_url: String = api_url.into();
+        if !api_url.ends_with('/') {
+            api_url.push('/');
+        }
+        Self {
+            api_url,
+            token: token.into(),
+        }
+    }
+
+    // The changeset id
+    pub async fn open_changeset(&self, comment: &str) -> Result<u64, Box<dyn std::error::Error>> {
+        let xml = format!(
+            "<osm><changeset><tag k=\"created_by\" v=\"osm_tb {}\"/><tag k=\"comment\" v=\"{}\"/></changeset></osm>",
+            env!("CARGO_PKG_VERSION"),
+            escape(comment)
+        );
+        let url = format!("{}changeset/create", self.api_url);
+        let answer = self.send(reqwest::Method::PUT, &url, xml).await?;
+        Ok(answer.trim().parse()?)
+    }
+
+    // The diffResult of the API
+    pub async fn upload_change(
+        &self,
+        changeset_id: u64,
+        osm_change: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let url = format!("{}changeset/{changeset_id}/upload", self.api_url);
+        self.send(reqwest::Method::POST, &url, osm_change.into())
+            .await
+    }
+
+    pub async fn close_changeset(
+        &self,
+        changeset_id: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let url = format!("{}changeset/{changeset_id}/close", self.api_url);
+        self.send(reqwest::Method::PUT, &url, String::new()).await?;
+        Ok(())
+    }
+
+    // All edits of the session in one changeset. It is closed, even if the upload failed
+    pub async fn upload(
+        &self,
+        session: &EditSession,
+        comment: &str,
+    ) -> Result<u64, Box<dyn std::error::Error>> {
+        let changeset_id = self.open_changeset(comment).await?;
+        let uploaded = match session.osm_change(changeset_id) {
+            Ok(osm_change) => self.upload_change(changeset_id, &osm_change).await,
+            Err(e) => Err(e.into()),
+        };
+        let closed = self.close_changeset(changeset_id).await;
+        uploaded?;
+        closed?;
+        Ok(changeset_id)
+    }
+
+    async fn send(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        xml: String,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        #[cfg(debug_assertions)]
+        println!("= Upload_URL: {method} {url}");
+        let response = reqwest::Client::new()
+            .request(method, url)
+            .bearer_auth(&self.token)
+            .header(reqwest::header::CONTENT_TYPE, "text/xml")
+            .body(xml)
+            .send()
+            .await?;
+        let status = response.status();
+        let answer = response.text().await?;
+        if !status.is_success() {
+            return Err(format!("{url}: {status} {answer}").into());
+        }
+        Ok(answer)
+    }
+}
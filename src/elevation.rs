@@ -0,0 +1,172 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Terrain elevation from local DEM tiles /////////////////////////////////////////////////////////
+
+/*
+ * SRTM .hgt tiles: https://wiki.openstreetmap.org/wiki/SRTM
+ * A tile covers one degree, named by its south west corner like N48E011.hgt.
+ * The heights are big-endian i16 meters, rows from north to south.
+ * 1201x1201 values are 3 arc seconds (about 90 m), 3601x3601 are 1 arc second.
+ *
+ * GeoTIFF is not read yet. It needs a TIFF decoder, the crate has none.
+ * A directory with GeoTIFF files is an error, not to show flat ground instead.
+ * Convert the tiles: gdal_translate -of SRTMHGT input.tif N48E011.hgt
+ */
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use geo::MultiPolygon;
+
+use crate::kernel_in::GeographicCoordinates;
+use crate::projection::LocalProjection;
+
+static HGT_VOID: i16 = -32768; // no data, like water or radar shadow
+
+#[derive(Debug)]
+struct HgtTile {
+    size: usize, // values per row and column
+    heights: Vec<i16>,
+}
+
+impl HgtTile {
+    fn height(&self, row: usize, column: usize) -> Option<f64> {
+        let height = self.heights[row * self.size + column];
+        (height != HGT_VOID).then_some(height as f64)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Elevation {
+    // by the latitude and longitude of the south west corner
+    tiles: HashMap<(i32, i32), HgtTile>,
+}
+
+impl Elevation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // All .hgt files of the directory. Err on GeoTIFF files, other files are skipped
+    pub fn from_directory(directory: &Path) -> std::io::Result<Self> {
+        let mut elevation = Self::new();
+        for entry in std::fs::read_dir(directory)? {
+            let path = entry?.path();
+            let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let extension = path.extension().and_then(|extension| extension.to_str());
+            match extension
+                .map(|extension| extension.to_lowercase())
+                .as_deref()
+            {
+                Some("hgt") => elevation.add_hgt(name, &std::fs::read(&path)?),
+                Some("tif") | Some("tiff") => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::Unsupported,
+                        format!("GeoTIFF is not supported, convert it to .hgt: {name}"),
+                    ));
+                }
+                _ => (),
+            }
+        }
+        Ok(elevation)
+    }
+
+    // A tile by its file name like N48E011.hgt and its bytes
+    pub fn add_hgt(&mut self, name: &str, bytes: &[u8]) {
+        let Some(corner) = parse_hgt_name(name) else {
+            println!("Not an SRTM tile name: {name}");
+            return;
+        };
+        let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+        if size < 2 || size * size * 2 != bytes.len() {
+            println!("Not an SRTM tile size: {name} {} bytes", bytes.len());
+            return;
+        }
+        let heights = bytes
+            .chunks_exact(2)
+            .map(|pair| i16::from_be_bytes([pair[0], pair[1]]))
+            .collect();
+        self.tiles.insert(corner, HgtTile { size, heights });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    // Meters above sea level, bilinear between the four nearest values. None without a tile
+    pub fn height_at(&self, coordinates: &GeographicCoordinates) -> Option<f64> {
+        let south = coordinates.latitude.floor();
+        let west = coordinates.longitude.floor();
+        let tile = self.tiles.get(&(south as i32, west as i32))?;
+        let cells = (tile.size - 1) as f64;
+        let row = (south + 1. - coordinates.latitude) * cells;
+        let column = (coordinates.longitude - west) * cells;
+        let row0 = (row.floor() as usize).min(tile.size - 2);
+        let column0 = (column.floor() as usize).min(tile.size - 2);
+        let row_share = row - row0 as f64;
+        let column_share = column - column0 as f64;
+
+        // Void values are left out, the others count more
+        let mut sum = 0.;
+        let mut weights = 0.;
+        for (row_offset, row_weight) in [(0, 1. - row_share), (1, row_share)] {
+            for (column_offset, column_weight) in [(0, 1. - column_share), (1, column_share)] {
+                if let Some(height) = tile.height(row0 + row_offset, column0 + column_offset) {
+                    let weight = row_weight * column_weight;
+                    sum += height * weight;
+                    weights += weight;
+                }
+            }
+        }
+        (weights > 0.).then(|| sum / weights)
+    }
+
+    // Relative to the GPU 0 position, which is at height 0
+    pub fn height_above_origin(
+        &self,
+        origin: &GeographicCoordinates,
+        coordinates: &GeographicCoordinates,
+    ) -> Option<f64> {
+        let origin_height = self.height_at(origin).unwrap_or(0.);
+        Some(self.height_at(coordinates)? - origin_height)
+    }
+
+    /*
+     * The lowest terrain below the corners of an outline, relative to the GPU 0 position.
+     * The building stands there, its walls reach down to it. Uphill, the terrain covers them
+     */
+    pub fn ground_height(
+        &self,
+        projection: &LocalProjection,
+        multipolygon: &MultiPolygon,
+    ) -> Option<f64> {
+        let origin = projection.origin();
+        multipolygon
+            .iter()
+            .flat_map(|polygon| polygon.exterior().coords())
+            .filter_map(|position| {
+                let coordinates = projection.inverse(*position);
+                self.height_above_origin(&origin, &coordinates)
+            })
+            .reduce(f64::min)
+    }
+}
+
+// N48E011.hgt => (48, 11)  S12W077.hgt => (-12, -77)
+fn parse_hgt_name(name: &str) -> Option<(i32, i32)> {
+    let name = name.get(..7)?.to_uppercase();
+    let latitude: i32 = name.get(1..3)?.parse().ok()?;
+    let longitude: i32 = name.get(4..7)?.parse().ok()?;
+    let latitude = match name.get(..1)? {
+        "N" => latitude,
+        "S" => -latitude,
+        _ => return None,
+    };
+    let longitude = match name.get(3..4)? {
+        "E" => longitude,
+        "W" => -longitude,
+        _ => return None,
+    };
+    Some((latitude, longitude))
+}
@@ -0,0 +1,151 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Facade: windows and level lines on the walls, optional to the 3D renderer //////////////////////
+
+use crate::kernel_in::{BuildingOrPart, Facade, GroundPosition, Structure};
+use crate::kernel_out::{RenderColor, RenderPosition};
+use crate::levels::DEFAULT_LEVEL_HEIGHT;
+use crate::symbolic_3d::to_gpu_position;
+
+// This constands may come from a (3D-)render shema
+pub static WINDOW_SPACING: f64 = 3.0; // one window each 3 meters of wall
+pub static WINDOW_WIDTH: f64 = 1.2;
+pub static WINDOW_COLOR: RenderColor = [0.20, 0.24, 0.32, 1.0]; // dark blue-grey
+pub static GLASS_COLOR: RenderColor = [0.45, 0.55, 0.65, 1.0];
+static LEVEL_LINE_HEIGHT: f64 = 0.12;
+static LEVEL_LINE_DARKEN: f32 = 0.75;
+// Windows and lines are not cut into the wall but put slightly in front of it. Otherwise they would flicker.
+static WINDOW_OFFSET: f64 = 0.05;
+static LEVEL_LINE_OFFSET: f64 = 0.03;
+
+// A facade rectangle in the same order as push_square expects it:  2---3
+#[derive(Clone, Debug)] //                                             |   |
+pub struct FacadeQuad {
+    //                                                                 0---1
+    pub down_left: RenderPosition,
+    pub down_right: RenderPosition,
+    pub up_left: RenderPosition,
+    pub up_right: RenderPosition,
+    pub color: RenderColor,
+}
+
+// Number and height of the visible levels of the wall between min_height and wall_height.
+// Levels below the ground have no windows
+pub fn wall_levels(building_or_part: &BuildingOrPart) -> (usize, f64) {
+    let wall = building_or_part.wall_height - building_or_part.min_height.max(0.);
+    if wall <= 0. {
+        return (0, 0.);
+    }
+    let levels = (building_or_part.levels - building_or_part.min_level).round();
+    if levels >= 1. {
+        return (levels as usize, wall / levels);
+    }
+    // Not tagged: as many levels as fit into the wall
+    let level_height = if building_or_part.level_height > 0. {
+        building_or_part.level_height
+    } else {
+        DEFAULT_LEVEL_HEIGHT
+    };
+    let levels = (wall / level_height).floor().max(1.);
+    (levels as usize, wall / levels)
+}
+
+/*
+ * Windows and level lines of one wall segment, from start to end.
+ * @param outward - unit vector on the ground, pointing away from the building
+ * @param top - the lower wall height of start and end. Levels above it are not dressed (skillion, gabled)
+ */
+pub fn facade_quads(
+    start: &GroundPosition,
+    end: &GroundPosition,
+    outward: GroundPosition,
+    building_or_part: &BuildingOrPart,
+    top: f64,
+) -> Vec<FacadeQuad> {
+    let mut quads = Vec::new();
+    let (levels, level_height) = wall_levels(building_or_part);
+    // Steps and roof edges have no levels
+    if levels == 0 || building_or_part.structure != Structure::Solid {
+        return quads;
+    }
+
+    let along = *end - *start;
+    let length = (along.x * along.x + along.y * along.y).sqrt();
+    if length < WINDOW_WIDTH {
+        return quads;
+    }
+    let along = along / length;
+    let min_height = building_or_part.min_height.max(0.);
+
+    for level in 0..levels {
+        let level_bottom = min_height + level as f64 * level_height;
+        let level_top = level_bottom + level_height;
+        if level_top > top + 0.01 {
+            break; // the rest of the wall is cut by the roof
+        }
+
+        // Level line at the lower edge of each level but the first
+        if level > 0 {
+            let mut color = building_or_part.building_color;
+            for channel in color.iter_mut().take(3) {
+                *channel *= LEVEL_LINE_DARKEN;
+            }
+            let line = *start + outward * LEVEL_LINE_OFFSET;
+            quads.push(quad(
+                &line,
+                &(line + along * length),
+                level_bottom - LEVEL_LINE_HEIGHT / 2.,
+                level_bottom + LEVEL_LINE_HEIGHT / 2.,
+                color,
+            ));
+        }
+
+        let sill = level_bottom + level_height * 0.3;
+        let lintel = level_bottom + level_height * 0.8;
+        let front = *start + outward * WINDOW_OFFSET;
+        match building_or_part.facade {
+            Facade::NoWindows => (),
+            Facade::Glass => {
+                let margin = (WINDOW_WIDTH / 4.).min(length / 4.);
+                quads.push(quad(
+                    &(front + along * margin),
+                    &(front + along * (length - margin)),
+                    level_bottom + level_height * 0.1,
+                    level_top - level_height * 0.1,
+                    GLASS_COLOR,
+                ));
+            }
+            Facade::Windows => {
+                let count = (length / WINDOW_SPACING).floor() as usize;
+                let spacing = length / count.max(1) as f64;
+                for window in 0..count {
+                    let center = (window as f64 + 0.5) * spacing;
+                    quads.push(quad(
+                        &(front + along * (center - WINDOW_WIDTH / 2.)),
+                        &(front + along * (center + WINDOW_WIDTH / 2.)),
+                        sill,
+                        lintel,
+                        WINDOW_COLOR,
+                    ));
+                }
+            }
+        }
+    }
+
+    quads
+}
+
+fn quad(
+    left: &GroundPosition,
+    right: &GroundPosition,
+    bottom: f64,
+    top: f64,
+    color: RenderColor,
+) -> FacadeQuad {
+    FacadeQuad {
+        down_left: to_gpu_position(left, bottom),
+        down_right: to_gpu_position(right, bottom),
+        up_left: to_gpu_position(left, top),
+        up_right: to_gpu_position(right, top),
+        color,
+    }
+}
@@ -11,6 +11,7 @@ use geo::algorithm::unary_union;
 use crate::kernel_in::{GroundPosition, GroundPositions};
 
 static _O: usize = 0; // Just to silent lint, make some lines equal and to show, the Offset may also be 0
+static MIN_SNAP_EDGE_LENGTH: f64 = 1.0; // Shorter edges are too small to give the roof a direction
 
 pub enum Orientation {
     None,
@@ -80,6 +81,12 @@ impl Footprint {
     }
 
     pub fn close(&mut self) {
+        // Less than 3 corners are no area
+        if self.outer_one.len() < 3 {
+            self.multipolygon = MultiPolygon::new(Vec::new());
+            self.outer_one = Vec::new();
+            return;
+        }
         self.bounding_box = LineString::new(self.outer_one.clone())
             .bounding_rect()
             .unwrap();
@@ -131,13 +138,19 @@ impl Footprint {
 
     pub fn rotate(&mut self, roof_angle: f64) -> Rect {
         // BoundingBox {
-        let polygon = self.multipolygon.iter().next().unwrap();
+        // An empty footprint, like a part subtracted completely, has no extent
+        let empty = Rect::new(self.center, self.center);
+        let Some(polygon) = self.multipolygon.iter().next() else {
+            return empty;
+        };
         let linestring = polygon.exterior();
         self.rotated_positions =
             linestring.rotate_around_point(roof_angle.to_degrees(), self.center.into());
 
         // println!(            "{:?} rotated_positions: {:?}",            self.outer_one.len(),            self.rotated_positions        );
-        let mut bounding_box_rotated = self.rotated_positions.bounding_rect().unwrap(); // BoundingBox::new();
+        let Some(mut bounding_box_rotated) = self.rotated_positions.bounding_rect() else {
+            return empty;
+        };
         let new_rotated_center_y = bounding_box_rotated.height() / 2.;
         // (bounding_box_rotated.north - bounding_box_rotated.south) / 2.0;
         let corretion_shift = new_rotated_center_y - bounding_box_rotated.max().y; // .north;
@@ -295,6 +308,83 @@ impl Footprint {
         (low, up)
     }
 
+    // Extent of the footprint along a direction (angle: 0 = north, clockwise), relative to the center
+    pub fn extent_along(&self, angle: f64) -> (f64, f64) {
+        let direction = GroundPosition {
+            x: angle.sin(),
+            y: angle.cos(),
+        };
+        let mut min = f64::MAX;
+        let mut max = f64::MIN;
+        for polygon in self.multipolygon.iter() {
+            for coord in polygon.exterior() {
+                let along =
+                    (*coord - self.center).x * direction.x + (*coord - self.center).y * direction.y;
+                min = min.min(along);
+                max = max.max(along);
+            }
+        }
+        if min > max {
+            return (0., 0.);
+        }
+        (min, max)
+    }
+
+    // The strip of the footprint between from and to, along a direction like in extent_along
+    pub fn slice_along(&self, angle: f64, from: f64, to: f64) -> MultiPolygon {
+        let direction = GroundPosition {
+            x: angle.sin(),
+            y: angle.cos(),
+        };
+        let across = GroundPosition {
+            x: direction.y,
+            y: -direction.x,
+        };
+        let wide = self.bounding_box.width() + self.bounding_box.height() + 1.;
+        let strip = Polygon::new(
+            LineString::new(vec![
+                self.center + direction * from - across * wide,
+                self.center + direction * to - across * wide,
+                self.center + direction * to + across * wide,
+                self.center + direction * from + across * wide,
+                self.center + direction * from - across * wide,
+            ]),
+            Vec::new(),
+        );
+        self.multipolygon.intersection(&strip)
+    }
+
+    /*
+     * The normal of the outer edge, closest to the angle (0 = north, clockwise), if within the tolerance.
+     * Both sides of an edge count, so a direction of a skillion roof may face out or in.
+     */
+    pub fn snap_to_edge_normal(&self, angle: f64, tolerance: f64) -> Option<f64> {
+        let mut best: Option<(f64, f64)> = None; // difference, normal
+        for polygon in self.multipolygon.iter() {
+            for edge in polygon.exterior().lines() {
+                if Euclidean.distance(edge.start, edge.end) < MIN_SNAP_EDGE_LENGTH {
+                    continue;
+                }
+                let edge_angle = f64::atan2(edge.dx(), edge.dy());
+                for normal in [
+                    edge_angle + f64::to_radians(90.),
+                    edge_angle - f64::to_radians(90.),
+                ] {
+                    let difference = angle_difference(angle, normal);
+                    if best.is_none_or(|(best_difference, _)| difference < best_difference) {
+                        best = Some((difference, normal));
+                    }
+                }
+            }
+        }
+        match best {
+            Some((difference, normal)) if difference <= tolerance => {
+                Some(normal.rem_euclid(f64::to_radians(360.)))
+            }
+            _ => None,
+        }
+    }
+
     // subttacting a hole of a polygon or a part inside a building - todo use the one line "inline"
     pub fn subtract(&mut self, other_a_hole: &Footprint) {
         // println!("### other_a_hole othr: {:?}", other_a_hole);
@@ -401,3 +491,9 @@ impl Footprint {
         remaining < 0.01
     }
 }
+
+// Smallest difference of two angles, 0 to PI
+pub fn angle_difference(a: f64, b: f64) -> f64 {
+    let difference = (a - b).rem_euclid(f64::to_radians(360.));
+    difference.min(f64::to_radians(360.) - difference)
+}
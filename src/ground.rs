@@ -0,0 +1,140 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Ground context: roads, water, landuse and trees around the buildings, coloured by tag //////////
+
+use crate::kernel_in::{OsmMap, RenderColor};
+use crate::length::parse_length;
+
+// Heights above the ground. A later layer covers an earlier one
+static LANDUSE_LEVEL: f64 = 0.02;
+static LEISURE_LEVEL: f64 = 0.03;
+static WATER_LEVEL: f64 = 0.04;
+static PAVED_LEVEL: f64 = 0.05;
+static ROAD_LEVEL: f64 = 0.06;
+static RAIL_LEVEL: f64 = 0.07;
+
+static WATER_COLOR: RenderColor = [0.45, 0.65, 0.95, 1.0];
+static GRASS_COLOR: RenderColor = [0.55, 0.85, 0.45, 1.0];
+static FOREST_COLOR: RenderColor = [0.25, 0.55, 0.25, 1.0];
+static FARMLAND_COLOR: RenderColor = [0.85, 0.9, 0.6, 1.0];
+static SAND_COLOR: RenderColor = [0.95, 0.9, 0.7, 1.0];
+static URBAN_COLOR: RenderColor = [0.85, 0.82, 0.8, 1.0];
+static INDUSTRIAL_COLOR: RenderColor = [0.8, 0.75, 0.8, 1.0];
+static PAVED_COLOR: RenderColor = [0.75, 0.75, 0.75, 1.0];
+static ROAD_COLOR: RenderColor = [0.4, 0.4, 0.4, 1.0];
+static MAJOR_ROAD_COLOR: RenderColor = [0.95, 0.75, 0.45, 1.0];
+static PATH_COLOR: RenderColor = [0.8, 0.6, 0.5, 1.0];
+static RAIL_COLOR: RenderColor = [0.3, 0.25, 0.25, 1.0];
+
+pub static TRUNK_COLOR: RenderColor = [0.45, 0.3, 0.2, 1.0];
+pub static CROWN_COLOR: RenderColor = [0.2, 0.5, 0.2, 1.0];
+static DEFAULT_TREE_HEIGHT: f64 = 8.;
+static DEFAULT_CROWN_DIAMETER: f64 = 4.;
+static LANE_WIDTH: f64 = 3.25;
+
+// The colour and level of an area like landuse=grass or natural=water. None if not shown
+pub fn classify_area(tags: &OsmMap) -> Option<(RenderColor, f64)> {
+    let get = |key: &str| tags.get(key).map(|value| value.as_str());
+
+    if get("natural") == Some("water")
+        || get("waterway") == Some("riverbank")
+        || matches!(get("landuse"), Some("reservoir" | "basin"))
+        || get("leisure") == Some("swimming_pool")
+    {
+        return Some((WATER_COLOR, WATER_LEVEL));
+    }
+    if (get("area") == Some("yes") && get("highway").is_some())
+        || get("area:highway").is_some()
+        || matches!(get("amenity"), Some("parking" | "marketplace"))
+        || matches!(get("place"), Some("square"))
+    {
+        return Some((PAVED_COLOR, PAVED_LEVEL));
+    }
+    if matches!(
+        get("leisure"),
+        Some("park" | "garden" | "pitch" | "playground" | "golf_course")
+    ) {
+        return Some((GRASS_COLOR, LEISURE_LEVEL));
+    }
+    match get("natural") {
+        Some("wood" | "scrub") => return Some((FOREST_COLOR, LANDUSE_LEVEL)),
+        Some("grassland" | "heath" | "wetland") => return Some((GRASS_COLOR, LANDUSE_LEVEL)),
+        Some("sand" | "beach") => return Some((SAND_COLOR, LANDUSE_LEVEL)),
+        _ => (),
+    }
+    let color = match get("landuse")? {
+        "grass" | "meadow" | "village_green" | "recreation_ground" | "cemetery" => GRASS_COLOR,
+        "forest" => FOREST_COLOR,
+        "farmland" | "orchard" | "vineyard" | "allotments" => FARMLAND_COLOR,
+        "residential" | "commercial" | "retail" | "construction" => URBAN_COLOR,
+        "industrial" | "railway" => INDUSTRIAL_COLOR,
+        _ => return None,
+    };
+    Some((color, LANDUSE_LEVEL))
+}
+
+/*
+ * The colour, width and level of a line like a highway or a river. None if not shown.
+ * The tag width counts first, then lanes, then a typical width of the highway class
+ */
+pub fn classify_line(tags: &OsmMap) -> Option<(RenderColor, f64, f64)> {
+    let get = |key: &str| tags.get(key).map(|value| value.as_str());
+    let tagged_width = get("width")
+        .and_then(|width| parse_length(width).ok())
+        .map(|width| width.meters);
+    let lanes_width = get("lanes")
+        .and_then(|lanes| lanes.parse::<f64>().ok())
+        .map(|lanes| lanes * LANE_WIDTH);
+    // Not on the ground. A passage through a building is
+    if get("tunnel").is_some_and(|tunnel| tunnel != "no" && tunnel != "building_passage") {
+        return None;
+    }
+
+    if let Some(highway) = get("highway") {
+        let (color, width) = match highway {
+            "motorway" | "trunk" => (MAJOR_ROAD_COLOR, 12.),
+            "primary" => (MAJOR_ROAD_COLOR, 9.),
+            "secondary" => (MAJOR_ROAD_COLOR, 7.),
+            "tertiary" => (ROAD_COLOR, 6.),
+            "residential" | "unclassified" | "living_street" | "road" => (ROAD_COLOR, 5.),
+            "pedestrian" => (PAVED_COLOR, 5.),
+            "service" | "track" => (ROAD_COLOR, 3.5),
+            "footway" | "path" | "cycleway" | "bridleway" | "steps" => (PATH_COLOR, 2.),
+            _ if highway.ends_with("_link") => (ROAD_COLOR, 5.),
+            _ => return None,
+        };
+        let width = tagged_width.or(lanes_width).unwrap_or(width);
+        return Some((color, width, ROAD_LEVEL));
+    }
+    if let Some(railway) = get("railway") {
+        let width = match railway {
+            "rail" | "light_rail" => 3.,
+            "tram" | "narrow_gauge" => 2.,
+            _ => return None,
+        };
+        return Some((RAIL_COLOR, tagged_width.unwrap_or(width), RAIL_LEVEL));
+    }
+    let width = match get("waterway")? {
+        "river" => 10.,
+        "canal" => 8.,
+        "stream" => 2.,
+        "ditch" | "drain" => 1.,
+        _ => return None,
+    };
+    Some((WATER_COLOR, tagged_width.unwrap_or(width), WATER_LEVEL))
+}
+
+// Height and crown diameter of a natural=tree node. None for other nodes
+pub fn tree_size(tags: &OsmMap) -> Option<(f64, f64)> {
+    if tags.get("natural").is_none_or(|natural| natural != "tree") {
+        return None;
+    }
+    let length = |key: &str| {
+        tags.get(key)
+            .and_then(|value| parse_length(value).ok())
+            .map(|length| length.meters)
+            .filter(|meters| *meters > 0.)
+    };
+    let height = length("height").unwrap_or(DEFAULT_TREE_HEIGHT);
+    let crown = length("diameter_crown").unwrap_or(DEFAULT_CROWN_DIAMETER.min(height));
+    Some((height, crown))
+}
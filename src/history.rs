@@ -0,0 +1,158 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// History of OSM elements: the data as it was at a version or date //////////////////////////////
+
+/*
+ * https://wiki.openstreetmap.org/wiki/API_v0.6#History:_GET_/api/0.6/[node|way|relation]/#id/history
+ * A history holds all versions of the elements. As of a time, the last version up to it
+ * is taken of each element. Deleted ones (visible: false) are left out.
+ * A version of the inspected element means: as of the time it was saved. Its nodes were
+ * saved before or in the same changeset, so they fit.
+ * The timestamps are compared as text. The API writes all alike: 2023-05-22T12:26:48Z
+ */
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use bytes::Bytes;
+use serde_json::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AsOf {
+    Latest,
+    Version(u64),      // of the inspected element
+    Timestamp(String), // like 2023-05-22T12:26:48Z
+}
+
+impl FromStr for AsOf {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        if text == "latest" {
+            return Ok(AsOf::Latest);
+        }
+        if let Ok(version) = text.parse() {
+            return Ok(AsOf::Version(version));
+        }
+        // A date includes the edits of that day
+        match text.len() {
+            10 if text.as_bytes()[4] == b'-' => Ok(AsOf::Timestamp(format!("{text}T23:59:59Z"))),
+            20 if text.ends_with('Z') => Ok(AsOf::Timestamp(text.into())),
+            _ => Err(format!(
+                "Unknown history point {text}, use: latest, a version, 2023-05-22 or 2023-05-22T12:26:48Z"
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for AsOf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AsOf::Latest => write!(f, "latest"),
+            AsOf::Version(version) => write!(f, "version {version}"),
+            AsOf::Timestamp(timestamp) => write!(f, "{timestamp}"),
+        }
+    }
+}
+
+// The elements, a history of them needs to be loaded too: the nodes and members
+pub(crate) fn history_references(element: &Value) -> Vec<(String, u64)> {
+    let mut references = Vec::new();
+    for node in element["nodes"].as_array().into_iter().flatten() {
+        if let Some(id) = node.as_u64() {
+            references.push(("node".into(), id));
+        }
+    }
+    for member in element["members"].as_array().into_iter().flatten() {
+        // Sub relations would load a whole region, like a city
+        if let (Some(member_type @ ("node" | "way")), Some(id)) =
+            (member["type"].as_str(), member["ref"].as_u64())
+        {
+            references.push((member_type.into(), id));
+        }
+    }
+    references
+}
+
+// The versions of an element and their timestamps, the oldest first
+pub fn history_versions(history: &[u8], element_type: &str, id: u64) -> Vec<(u64, String)> {
+    let Ok(json) = serde_json::from_slice::<Value>(history) else {
+        return Vec::new();
+    };
+    let mut versions: Vec<(u64, String)> = json["elements"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|element| is_element(element, element_type, id))
+        .filter_map(|element| {
+            Some((
+                element["version"].as_u64()?,
+                element["timestamp"].as_str()?.to_string(),
+            ))
+        })
+        .collect();
+    versions.sort();
+    versions
+}
+
+/*
+ * The history reduced to the data as of the version or time, like the API sends it now:
+ * OSM JSON bytes with nodes first, then ways and relations.
+ * None if the history is no JSON or does not have the version
+ */
+pub fn history_as_of(history: &[u8], as_of: &AsOf, element_type: &str, id: u64) -> Option<Bytes> {
+    let json: Value = serde_json::from_slice(history).ok()?;
+    let elements = json["elements"].as_array()?;
+    let until = match as_of {
+        AsOf::Latest => None,
+        AsOf::Timestamp(timestamp) => Some(timestamp.as_str()),
+        AsOf::Version(version) => elements.iter().find(|element| {
+            is_element(element, element_type, id) && element["version"].as_u64() == Some(*version)
+        })?["timestamp"]
+            .as_str(),
+    };
+
+    // The last version of each element up to the time
+    let mut last: HashMap<(&str, u64), &Value> = HashMap::new();
+    for element in elements {
+        let (Some(element_type), Some(id), Some(version)) = (
+            element["type"].as_str(),
+            element["id"].as_u64(),
+            element["version"].as_u64(),
+        ) else {
+            continue;
+        };
+        if let Some(until) = until
+            && element["timestamp"]
+                .as_str()
+                .is_none_or(|timestamp| timestamp > until)
+        {
+            continue;
+        }
+        let newer = last
+            .get(&(element_type, id))
+            .is_none_or(|other| other["version"].as_u64() < Some(version));
+        if newer {
+            last.insert((element_type, id), element);
+        }
+    }
+
+    let mut elements: Vec<&Value> = last
+        .into_values()
+        .filter(|element| element["visible"].as_bool() != Some(false))
+        .collect();
+    // The ways need their nodes before them
+    elements.sort_by_key(|element| {
+        let rank = match element["type"].as_str() {
+            Some("node") => 0,
+            Some("way") => 1,
+            _ => 2,
+        };
+        (rank, element["id"].as_u64())
+    });
+    let as_of = serde_json::json!({ "elements": elements });
+    Some(Bytes::from(serde_json::to_vec(&as_of).ok()?))
+}
+
+fn is_element(element: &Value, element_type: &str, id: u64) -> bool {
+    element["type"].as_str() == Some(element_type) && element["id"].as_u64() == Some(id)
+}
@@ -1,13 +1,25 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
 use bytes::*;
 use geo::{BoundingRect, LineString};
 use serde::Deserialize;
 
+use crate::diagnostics::Diagnostics;
+use crate::elevation::Elevation;
+use crate::history::history_references;
 use crate::kernel_in::{
-    BoundingBox, BuildingsAndParts, GeographicCoordinates, GroundPosition, Members, OsmMap,
+    BoundingBox, BuildingsAndParts, GeographicCoordinates, GroundFeatures, GroundPosition, Members,
+    OsmMap,
 };
-use crate::osm2layers::{Osm2Layer, tags_get_yes};
+use crate::osm_change::OsmChange;
+use crate::osm2layers::{MissingElements, Osm2Layer, tags_get_yes};
+use crate::profile::InterpretationProfile;
+use crate::response_cache::{CachedResponse, ResponseCache, element_versions, iso_time};
 
-const LOCAL_TEST: bool = false;
+static OSM_API_URL: &str = "https://api.openstreetmap.org/api/0.6/";
+static MISSING_IDS_PER_REQUEST: usize = 200; // keeps the URL short
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 // JOSN ///////////////////////////////////////////////////////////////////////////////////////////
@@ -15,6 +27,25 @@ const LOCAL_TEST: bool = false;
 #[derive(Debug)]
 pub struct InputOsm {
     api_url: String,
+    // Stored responses instead of the API: way.json or relation.json and bbox.json
+    directory: Option<PathBuf>,
+    profile: InterpretationProfile,
+    elevation: Option<Arc<Elevation>>,
+    completion_passes: usize,
+    cache: Option<ResponseCache>,
+}
+
+// How get decides if a cached response without an ETag is still valid
+#[derive(Debug, Clone, Copy)]
+enum Validation {
+    // All elements in it have the same versions as in the API: elements, histories, multi fetches
+    Versions,
+    // No changeset touched the area since the response was cached: map data by bounding box
+    Area(BoundingBox),
+    // Versions of elements never change
+    Unchanging,
+    // Only the ETag tells, without it the response is loaded again
+    ETag,
 }
 
 impl Default for InputOsm {
@@ -25,12 +56,85 @@ impl Default for InputOsm {
 
 impl InputOsm {
     pub fn new() -> Self {
-        let api_url = "https://api.openstreetmap.org/api/0.6/".into();
-        Self { api_url }
+        Self {
+            api_url: OSM_API_URL.into(),
+            directory: None,
+            profile: InterpretationProfile::default(),
+            elevation: None,
+            completion_passes: 0,
+            cache: None,
+        }
+    }
+
+    /*
+     * Another API, like the dev API https://master.apis.dev.openstreetmap.org/api/0.6/
+     * or a local mock server. The URL ends with the version, like /api/0.6/
+     */
+    pub fn with_base_url(mut self, api_url: &str) -> Self {
+        self.api_url = api_url.into();
+        if !self.api_url.ends_with('/') {
+            self.api_url.push('/');
+        }
+        self
+    }
+
+    pub fn api_url(&self) -> &str {
+        &self.api_url
+    }
+
+    // The fixture directory, None if the API is used
+    pub fn directory(&self) -> Option<&Path> {
+        self.directory.as_deref()
+    }
+
+    /*
+     * Fixtures instead of the API, like in assets/: way.json or relation.json of the element
+     * and bbox.json of the area around. One element per directory, the ids are not checked
+     */
+    pub fn from_directory(directory: &Path) -> Self {
+        Self {
+            directory: Some(directory.into()),
+            ..Self::new()
+        }
+    }
+
+    // Read the tags like F4map or OSM2World do
+    pub fn with_profile(mut self, profile: InterpretationProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    // Place the buildings on the terrain. Shared, because the tiles are large
+    pub fn with_elevation(mut self, elevation: Arc<Elevation>) -> Self {
+        self.elevation = Some(elevation);
+        self
+    }
+
+    /*
+     * Load the nodes and ways, missing in the bbox data, like the members of a large relation.
+     * Loaded ways need their nodes, so the next pass loads them. 0 passes: no loading
+     */
+    pub fn with_completion(mut self, passes: usize) -> Self {
+        self.completion_passes = passes;
+        self
+    }
+
+    /*
+     * Keep the responses on disk. The element is loaded again, if its version or one of its
+     * nodes or members changed. The area around it is loaded again, if a changeset touched it
+     * since it was cached. Use ResponseCache::with_refresh to load all again anyway
+     */
+    pub fn with_cache(mut self, cache: ResponseCache) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     pub fn element_url(&self, way_id: u64, is_way: bool) -> String {
         let way_or_relation = if is_way { "way" } else { "relation" };
+        if let Some(directory) = &self.directory {
+            let file = directory.join(format!("{way_or_relation}.json"));
+            return file.to_string_lossy().into();
+        }
         format!("{}{}/{}/full.json", self.api_url, way_or_relation, way_id)
     }
 
@@ -38,6 +142,9 @@ impl InputOsm {
         // https://wiki.openstreetmap.org/wiki/API_v0.6#Retrieving_map_data_by_bounding_box:_GET_/api/0.6/map
         // GET   /api/0.6/map.json?bbox=left,bottom,right,top
         // .......api/0.6/map.json?bbox=11.0861309,49.7151912,11.0862858,49.7152795
+        if let Some(directory) = &self.directory {
+            return directory.join("bbox.json").to_string_lossy().into();
+        }
         format!(
             "{}map.json?bbox={},{},{},{}",
             self.api_url,
@@ -48,50 +155,101 @@ impl InputOsm {
         )
     }
 
+    pub fn history_url(&self, element_type: &str, id: u64) -> String {
+        // A history dump of all elements, like the one load_history writes
+        if let Some(directory) = &self.directory {
+            return directory.join("history.json").to_string_lossy().into();
+        }
+        format!("{}{element_type}/{id}/history.json", self.api_url)
+    }
+
+    // osmChange XML, there is no JSON of it
+    pub fn changeset_url(&self, changeset_id: u64) -> String {
+        if let Some(directory) = &self.directory {
+            return directory.join("changeset.osc").to_string_lossy().into();
+        }
+        format!("{}changeset/{changeset_id}/download", self.api_url)
+    }
+
+    // https://wiki.openstreetmap.org/wiki/API_v0.6#Multi_fetch:_GET_/api/0.6/[nodes|ways|relations]?#parameters
+    pub fn missing_urls(&self, missing: &MissingElements) -> Vec<String> {
+        let mut urls = Vec::new();
+        // The fixtures are all there is
+        if self.directory.is_some() {
+            return urls;
+        }
+        for (elements, ids) in [("nodes", &missing.nodes), ("ways", &missing.ways)] {
+            urls.extend(self.multi_fetch_urls(elements, ids));
+        }
+        urls
+    }
+
+    // Like nodes.json?nodes=1v2,3v4, a version of each element
+    fn version_urls(&self, versions: &[(String, u64, u64)]) -> Vec<String> {
+        if let Some(directory) = &self.directory {
+            return vec![directory.join("history.json").to_string_lossy().into()];
+        }
+        let mut ids: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+        for (element_type, id, version) in versions {
+            ids.entry(element_type)
+                .or_default()
+                .push(format!("{id}v{version}"));
+        }
+        let mut urls = Vec::new();
+        for (element_type, ids) in ids {
+            for chunk in ids.chunks(MISSING_IDS_PER_REQUEST) {
+                urls.push(format!(
+                    "{}{element_type}s.json?{element_type}s={}",
+                    self.api_url,
+                    chunk.join(",")
+                ));
+            }
+        }
+        urls
+    }
+
+    // Like nodes.json?nodes=1,2,3 in chunks
+    fn multi_fetch_urls(&self, elements: &str, ids: &BTreeSet<u64>) -> Vec<String> {
+        let ids: Vec<String> = ids.iter().map(|id| id.to_string()).collect();
+        ids.chunks(MISSING_IDS_PER_REQUEST)
+            .map(|chunk| {
+                format!(
+                    "{}{elements}.json?{elements}={}",
+                    self.api_url,
+                    chunk.join(",")
+                )
+            })
+            .collect()
+    }
+
     pub async fn geo_bbox_of_element(
         &self,
         way_id: u64,
         is_way: bool,
     ) -> Result<BoundingBox, Box<dyn std::error::Error>> {
-        let mut url = self.element_url(way_id, is_way); // format!("{}way/{}/full.json", self.api_url, way_id);
-        if LOCAL_TEST {
-            url = "bbox.json".into();
-        }
+        let url = self.element_url(way_id, is_way); // format!("{}way/{}/full.json", self.api_url, way_id);
 
         #[cfg(debug_assertions)]
         println!("= Way_URL: {url}");
 
-        let response = reqwest::get(url).await?;
-        println!("= one element status: {}", response.status());
-        match response.status().as_u16() {
+        let (status, bytes) = self.get(&url, Validation::Versions).await?;
+        println!("= one element status: {}", status);
+        match status {
             200 => (),
             404 => println!("Way {} does not exist (404)", way_id),
             410 => println!("Way {} is deleted (410)", way_id),
-            _ => panic!("Way read error {:?}", response.status().as_u16()),
-        }
-        let result = response.bytes().await; //e?;
-        //println!("result1: {:?}", result);
-
-        match result {
-            // this code is messy, isnt it ??? replace by crate geo
-            Ok(bytes) => {
-                let option = geo_bbox_of_way_bytes(&bytes, way_id, is_way);
-                if let Some(bounding_box) = option {
-                    Ok(bounding_box)
-                } else {
-                    Ok(geo::Rect::new(
-                        GroundPosition::zero(),
-                        GroundPosition::zero(),
-                    ))
-                }
-            }
-            Err(e) => {
-                println!("Way bytes Loading Error: {}", e);
-                Ok(geo::Rect::new(
-                    GroundPosition::zero(),
-                    GroundPosition::zero(),
-                ))
-            }
+            _ => panic!("Way read error {:?}", status),
+        }
+
+        // this code is messy, isnt it ??? replace by crate geo
+        let option = geo_bbox_of_way_bytes(&bytes, way_id, is_way);
+        if let Some(bounding_box) = option {
+            Ok(bounding_box)
+        } else {
+            Ok(geo::Rect::new(
+                GroundPosition::zero(),
+                GroundPosition::zero(),
+            ))
         }
 
         // Ok(geo_bbox_of_way_bytes(&bytes))
@@ -109,38 +267,327 @@ impl InputOsm {
         show_only: u64,
         way_only: u64,
     ) -> Result<BuildingsAndParts, Box<dyn std::error::Error>> {
-        let mut url = self.bbox_url(bounding_box);
+        let bytes = self.load_osm(bounding_box).await?;
+        let Ok(json_bbox_data) = serde_json::from_slice(&bytes) else {
+            return Ok(Vec::new());
+        };
+        Ok(self.scan_json(
+            json_bbox_data,
+            gpu_ground_null_coordinates,
+            show_only,
+            way_only,
+        ))
+    }
+
+    // The JSON bytes of the area, to scan them once or more often, like with different profiles
+    pub async fn load_osm(
+        &self,
+        bounding_box: &BoundingBox,
+    ) -> Result<Bytes, Box<dyn std::error::Error>> {
+        let url = self.bbox_url(bounding_box);
         //let mut url = format!("{}map.json?bbox={:?}", self.api_url, bounding_box);
-        if LOCAL_TEST {
-            url = "way.json".into();
-        }
 
         #[cfg(debug_assertions)]
         println!("= BBox_URL: {url}");
 
         // let bytes = reqwest::get(url).await?.bytes().await?;
-        let response = reqwest::get(url).await?;
-        match response.status().as_u16() {
+        let validation = Validation::Area(*bounding_box);
+        let (status, bytes) = self.get(&url, validation).await?;
+        match status {
             200 => (),
             400 => println!("Bad Request: Map limits are exceeded (400)"),
             509 => println!("Bandwidth Limit Exceeded: too much data downloaded (509)"),
-            _ => panic!("Load Map Error: {:?}", response.status().as_u16()),
-        }
-        let bytes = response.bytes().await;
-
-        match bytes {
-            Ok(bytes) => Ok(scan_json_bytes_to_osm(
-                bytes,
-                gpu_ground_null_coordinates,
-                show_only,
-                way_only,
-            )),
-            Err(e) => {
-                println!("Area bytes Loading Error: {}", e);
-                panic!("Area bytes Loading Error: {:?}", e);
-                //Error(e)
+            _ => panic!("Load Map Error: {:?}", status),
+        }
+
+        if self.completion_passes > 0 {
+            return self.complete_osm(bytes).await;
+        }
+        Ok(bytes)
+    }
+
+    /*
+     * All versions of the element and of the ways and nodes it ever used, as OSM JSON.
+     * One request per element, the API has no multi fetch for histories.
+     * Take a version or time out of it by history_as_of
+     */
+    pub async fn load_history(
+        &self,
+        element_id: u64,
+        is_way: bool,
+    ) -> Result<Bytes, Box<dyn std::error::Error>> {
+        let way_or_relation = if is_way { "way" } else { "relation" };
+        if self.directory.is_some() {
+            let (_status, bytes) = self
+                .get(
+                    &self.history_url(way_or_relation, element_id),
+                    Validation::Versions,
+                )
+                .await?;
+            return Ok(bytes);
+        }
+
+        let mut elements = Vec::new();
+        let mut loaded = BTreeSet::new();
+        let mut to_load = vec![(way_or_relation.to_string(), element_id)];
+        while let Some((element_type, id)) = to_load.pop() {
+            if !loaded.insert((element_type.clone(), id)) {
+                continue;
+            }
+            let url = self.history_url(&element_type, id);
+            let (status, bytes) = self.get(&url, Validation::Versions).await?;
+            if status != 200 {
+                println!("History of {element_type} {id} not loaded: {status}");
+                continue;
+            }
+            let history: serde_json::Value = serde_json::from_slice(&bytes)?;
+            for element in history["elements"].as_array().into_iter().flatten() {
+                to_load.extend(history_references(element));
+                elements.push(element.clone());
+            }
+        }
+        println!(
+            "History loaded: {} elements, {} versions",
+            loaded.len(),
+            elements.len()
+        );
+        let history = serde_json::json!({ "elements": elements });
+        Ok(Bytes::from(serde_json::to_vec(&history)?))
+    }
+
+    pub async fn load_changeset(
+        &self,
+        changeset_id: u64,
+    ) -> Result<OsmChange, Box<dyn std::error::Error>> {
+        let url = self.changeset_url(changeset_id);
+        let (status, bytes) = self.get(&url, Validation::ETag).await?;
+        if status != 200 {
+            return Err(format!("Changeset {changeset_id} not loaded: {status}").into());
+        }
+        Ok(OsmChange::from_xml(std::str::from_utf8(&bytes)?)?)
+    }
+
+    /*
+     * The modified and deleted elements of an uploaded changeset as they were before it,
+     * to revert the loaded data by OsmChange::revert. Fixtures: from history.json
+     */
+    pub async fn load_previous_versions(
+        &self,
+        osm_change: &OsmChange,
+    ) -> Result<Vec<serde_json::Value>, Box<dyn std::error::Error>> {
+        let versions = osm_change.previous_versions();
+        let mut previous = Vec::new();
+        for url in self.version_urls(&versions) {
+            #[cfg(debug_assertions)]
+            println!("= Version_URL: {url}");
+            let (status, bytes) = self.get(&url, Validation::Unchanging).await?;
+            // Redacted versions fail the whole request
+            if status != 200 {
+                println!("Previous versions not loaded: {status}");
+                continue;
+            }
+            let loaded: serde_json::Value = serde_json::from_slice(&bytes)?;
+            for element in loaded["elements"].as_array().into_iter().flatten() {
+                let (Some(element_type), Some(id), Some(version)) = (
+                    element["type"].as_str(),
+                    element["id"].as_u64(),
+                    element["version"].as_u64(),
+                ) else {
+                    continue;
+                };
+                if versions.contains(&(element_type.into(), id, version)) {
+                    previous.push(element.clone());
+                }
+            }
+        }
+        Ok(previous)
+    }
+
+    // The bytes with the missing elements added, loaded in up to completion_passes
+    async fn complete_osm(&self, bytes: Bytes) -> Result<Bytes, Box<dyn std::error::Error>> {
+        let Ok(mut json) = serde_json::from_slice::<serde_json::Value>(&bytes) else {
+            return Ok(bytes);
+        };
+        for pass in 0..self.completion_passes {
+            let Ok(json_bbox_data) = serde_json::from_value::<JsonData>(json.clone()) else {
+                break;
+            };
+            let missing = missing_elements_json(json_bbox_data);
+            if missing.is_empty() {
+                break;
+            }
+            println!(
+                "Completion pass {}: {} nodes and {} ways missing",
+                pass + 1,
+                missing.nodes.len(),
+                missing.ways.len()
+            );
+            for url in self.missing_urls(&missing) {
+                #[cfg(debug_assertions)]
+                println!("= Missing_URL: {url}");
+                let (status, bytes) = self.get(&url, Validation::Versions).await?;
+                // Deleted or hidden elements fail the whole request
+                if status != 200 {
+                    println!("Missing elements not loaded: {}", status);
+                    continue;
+                }
+                let loaded: serde_json::Value = serde_json::from_slice(&bytes)?;
+                if let (Some(elements), Some(loaded)) = (
+                    json["elements"].as_array_mut(),
+                    loaded["elements"].as_array(),
+                ) {
+                    elements.extend(loaded.iter().cloned());
+                }
+            }
+            // The ways need their nodes before them, the loaded ones are at the end
+            if let Some(elements) = json["elements"].as_array_mut() {
+                elements.sort_by_key(|element| match element["type"].as_str() {
+                    Some("node") => 0,
+                    Some("way") => 1,
+                    _ => 2,
+                });
             }
         }
+        Ok(Bytes::from(serde_json::to_vec(&json)?))
+    }
+
+    // The status and bytes of the URL or fixture file, from the cache if they are still valid
+    async fn get(
+        &self,
+        url: &str,
+        validation: Validation,
+    ) -> Result<(u16, Bytes), Box<dyn std::error::Error>> {
+        if self.directory.is_some() {
+            return match std::fs::read(url) {
+                Ok(bytes) => Ok((200, Bytes::from(bytes))),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok((404, Bytes::new())),
+                Err(e) => Err(e.into()),
+            };
+        }
+        let Some(cache) = &self.cache else {
+            let response = reqwest::get(url).await?;
+            let status = response.status().as_u16();
+            return Ok((status, response.bytes().await?));
+        };
+
+        let cached = cache.read(url);
+        let valid = match &cached {
+            Some(_) if cache.offline() => true,
+            Some(cached) => self.cached_is_valid(cached, validation).await,
+            None if cache.offline() => {
+                return Err(format!("Offline, not in the cache: {url}").into());
+            }
+            None => false,
+        };
+        if let (true, Some(cached)) = (valid, &cached) {
+            #[cfg(debug_assertions)]
+            println!("= Cached: {url}");
+            return Ok((200, cached.bytes.clone()));
+        }
+
+        let mut request = reqwest::Client::new().get(url);
+        if let Some(etag) = cached.as_ref().and_then(|cached| cached.etag.as_ref()) {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        let response = request.send().await?;
+        let status = response.status().as_u16();
+        if let (304, Some(cached)) = (status, cached) {
+            return Ok((200, cached.bytes)); // Not Modified
+        }
+        let etag = response
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_string());
+        let bytes = response.bytes().await?;
+        if status == 200
+            && let Err(e) = cache.write(url, &bytes, etag.as_deref())
+        {
+            println!("Response not cached: {e}");
+        }
+        Ok((status, bytes))
+    }
+
+    /*
+     * With an ETag, the API decides by the conditional request.
+     * Elements are valid if each of them has the same version as in the API, asked by
+     * multi fetches of the full elements. A moved node or an edited member changes only
+     * their own version, so full.json lists them too.
+     * The map data of an area is valid if no changeset was open in it since it was cached.
+     * Without a connection, the cached response is the best there is
+     */
+    async fn cached_is_valid(&self, cached: &CachedResponse, validation: Validation) -> bool {
+        if cached.etag.is_some() {
+            return false;
+        }
+        match validation {
+            Validation::Versions => self.versions_are_current(&cached.bytes).await,
+            Validation::Area(bounding_box) => match cached.written {
+                Some(written) => self.area_is_unchanged(&bounding_box, written).await,
+                None => false,
+            },
+            Validation::Unchanging => true,
+            Validation::ETag => false,
+        }
+    }
+
+    async fn versions_are_current(&self, bytes: &[u8]) -> bool {
+        let cached_versions = element_versions(bytes);
+        if cached_versions.is_empty() {
+            return false;
+        }
+        let mut ids: BTreeMap<&str, BTreeSet<u64>> = BTreeMap::new();
+        for (element_type, id) in cached_versions.keys() {
+            ids.entry(element_type).or_default().insert(*id);
+        }
+        let mut current_versions = HashMap::new();
+        for (element_type, ids) in ids {
+            for url in self.multi_fetch_urls(&format!("{element_type}s"), &ids) {
+                let current = match reqwest::get(&url).await {
+                    Ok(response) if response.status().as_u16() == 200 => {
+                        response.bytes().await.ok()
+                    }
+                    // Deleted or hidden elements fail the whole request
+                    Ok(_) => None,
+                    Err(_) => return true,
+                };
+                let Some(current) = current else {
+                    return false;
+                };
+                current_versions.extend(element_versions(&current));
+            }
+        }
+        current_versions == cached_versions
+    }
+
+    // https://wiki.openstreetmap.org/wiki/API_v0.6#Query:_GET_/api/0.6/changesets
+    async fn area_is_unchanged(&self, bounding_box: &BoundingBox, written: u64) -> bool {
+        // The changesets closed after the time, and the open ones.
+        // A minute earlier, for the time the API took to answer
+        let url = format!(
+            "{}changesets.json?bbox={},{},{},{}&time={}",
+            self.api_url,
+            bounding_box.min().x,
+            bounding_box.min().y,
+            bounding_box.max().x,
+            bounding_box.max().y,
+            iso_time(written.saturating_sub(60)),
+        );
+        #[cfg(debug_assertions)]
+        println!("= Changesets_URL: {url}");
+        let changesets = match reqwest::get(&url).await {
+            Ok(response) if response.status().as_u16() == 200 => response.bytes().await.ok(),
+            Ok(_) => None,
+            Err(_) => return true,
+        };
+        let Some(changesets) =
+            changesets.and_then(|bytes| serde_json::from_slice::<serde_json::Value>(&bytes).ok())
+        else {
+            return false;
+        };
+        changesets["changesets"]
+            .as_array()
+            .is_some_and(|changesets| changesets.is_empty())
     }
 
     pub fn scan_json_to_osm_vec(
@@ -151,13 +598,60 @@ impl InputOsm {
         way_only: u64,
     ) -> BuildingsAndParts {
         let json_bbox_data: JsonData = serde_json::from_slice(bytes).unwrap();
-        scan_json_to_osm(
+        self.scan_json(
             json_bbox_data,
             gpu_ground_null_coordinates,
             show_only,
             way_only,
         )
     }
+
+    // Like scan_json_to_osm_vec, with the roads, water, landuse and trees around
+    pub fn scan_json_to_layers_vec(
+        &self,
+        bytes: &[u8],
+        gpu_ground_null_coordinates: &GeographicCoordinates,
+        show_only: u64,
+        way_only: u64,
+    ) -> (BuildingsAndParts, GroundFeatures) {
+        let Ok(json_bbox_data) = serde_json::from_slice(bytes) else {
+            return (Vec::new(), Vec::new());
+        };
+        let osm2layer = self.create_layer(gpu_ground_null_coordinates, show_only, way_only);
+        scan_json_into_layer(json_bbox_data, osm2layer).get_buildings_and_parts_and_ground()
+    }
+
+    fn scan_json(
+        &self,
+        json_bbox_data: JsonData,
+        gpu_ground_null_coordinates: &GeographicCoordinates,
+        show_only: u64,
+        way_only: u64,
+    ) -> BuildingsAndParts {
+        let osm2layer = self.create_layer(gpu_ground_null_coordinates, show_only, way_only);
+        let (buildings_and_parts, _diagnostics) = scan_json_into_layer(json_bbox_data, osm2layer)
+            .get_buildings_and_parts_and_diagnostics();
+        #[cfg(debug_assertions)]
+        for diagnostic in &_diagnostics {
+            println!("{diagnostic}");
+        }
+        buildings_and_parts
+    }
+
+    // With the profile and elevation of this input
+    fn create_layer(
+        &self,
+        gpu_ground_null_coordinates: &GeographicCoordinates,
+        show_only: u64,
+        way_only: u64,
+    ) -> Osm2Layer {
+        let mut osm2layer = Osm2Layer::create(*gpu_ground_null_coordinates, show_only, way_only);
+        osm2layer.set_profile(self.profile);
+        if let Some(elevation) = &self.elevation {
+            osm2layer.set_elevation(elevation.clone());
+        }
+        osm2layer
+    }
 }
 
 // todo: &str   https://users.rust-lang.org/t/requires-that-de-must-outlive-static-issue/91344/10
@@ -173,7 +667,7 @@ pub struct JosnElement {
     tags: Option<OsmMap>,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct JsonData {
     pub elements: Vec<JosnElement>,
 }
@@ -202,18 +696,17 @@ pub fn geo_bbox_of_element_json(
 
     // println!("Received JSON: {}", json_way),
     // let mut bounding_box = BoundingBox::new();
+    let way_or_relation = if is_way { "way" } else { "relation" };
     let mut positions = Vec::new();
     // add the coordinates of all nodes
     for element in json_way_data.elements {
-        if (element.element_type == "way" && element.id == element_id && is_way)
-            || (element.element_type == "relation" && element.id == element_id && !is_way)
+        if element.element_type == way_or_relation
+            && element.id == element_id
+            && let Some(tags) = element.tags
+            && tags_get_yes(&tags, "building:part").is_some()
         {
-            if let Some(tags) = element.tags {
-                if tags_get_yes(&tags, "building:part").is_some() {
-                    println!("Inspected Way is not a building but a part!");
-                    return geo::Rect::new(GroundPosition::zero(), GroundPosition::zero());
-                }
-            }
+            println!("Inspected Way is not a building but a part!");
+            return geo::Rect::new(GroundPosition::zero(), GroundPosition::zero());
         }
 
         if element.element_type == "node" {
@@ -231,6 +724,15 @@ pub fn geo_bbox_of_element_json(
     LineString::new(positions).bounding_rect().unwrap()
 }
 
+// The ids of the nodes and ways, referenced in the data, but not in it
+pub fn missing_elements_json(json_bbox_data: JsonData) -> MissingElements {
+    // Without GPU 0 position, the projection does not matter here
+    let osm2layer = Osm2Layer::create(GeographicCoordinates::default(), 0, 0);
+    scan_json_into_layer(json_bbox_data, osm2layer)
+        .missing_elements()
+        .clone()
+}
+
 pub fn scan_json_bytes_to_osm(
     bytes: Bytes,
     gpu_ground_null_coordinates: &GeographicCoordinates,
@@ -257,19 +759,55 @@ pub fn scan_json_to_osm(
     show_only: u64,
     way_only: u64,
 ) -> BuildingsAndParts {
+    let (buildings_and_parts, _diagnostics) = scan_json_to_osm_and_diagnostics(
+        json_bbox_data,
+        gpu_ground_null_coordinates,
+        show_only,
+        way_only,
+        InterpretationProfile::default(),
+    );
+    #[cfg(debug_assertions)]
+    for diagnostic in &_diagnostics {
+        println!("{diagnostic}");
+    }
+    buildings_and_parts
+}
+
+// Like scan_json_to_osm, with the tagging problems, found on the way
+pub fn scan_json_to_osm_and_diagnostics(
+    json_bbox_data: JsonData,
+    gpu_ground_null_coordinates: &GeographicCoordinates,
+    show_only: u64,
+    way_only: u64,
+    profile: InterpretationProfile,
+) -> (BuildingsAndParts, Diagnostics) {
     let mut osm2layer = Osm2Layer::create(*gpu_ground_null_coordinates, show_only, way_only);
+    osm2layer.set_profile(profile);
+    scan_json_into_layer(json_bbox_data, osm2layer).get_buildings_and_parts_and_diagnostics()
+}
+
+// The elements into a prepared Osm2Layer, like one with a profile and elevation
+fn scan_json_into_layer(json_bbox_data: JsonData, mut osm2layer: Osm2Layer) -> Osm2Layer {
     for element in json_bbox_data.elements {
         // println!("id: {}  type: {}", element.id, element.element_type);
+        // Broken or deleted elements have no coordinates, nodes or members. They are skipped
         match element.element_type.as_str() {
-            "node" => {
-                osm2layer.add_node(element.id, element.lat.unwrap(), element.lon.unwrap(), None)
-            }
+            "node" => match (element.lat, element.lon) {
+                (Some(latitude), Some(longitude)) => {
+                    osm2layer.add_node(element.id, latitude, longitude, element.tags)
+                }
+                _ => println!("Node without coordinates: {}", element.id),
+            },
 
-            "way" => osm2layer.add_way(element.id, element.nodes.unwrap(), element.tags),
+            "way" => match element.nodes {
+                Some(nodes) => osm2layer.add_way(element.id, nodes, element.tags),
+                None => println!("Way without nodes: {}", element.id),
+            },
 
-            "relation" => {
-                osm2layer.add_relation(element.id, element.members.unwrap(), element.tags)
-            }
+            "relation" => match element.members {
+                Some(members) => osm2layer.add_relation(element.id, members, element.tags),
+                None => println!("Relation without members: {}", element.id),
+            },
 
             _ => println!(
                 "Error: Unknown element type: {}  id: {}",
@@ -279,6 +817,5 @@ pub fn scan_json_to_osm(
     }
 
     osm2layer.process_elements();
-
-    osm2layer.get_buildings_and_parts()
+    osm2layer
 }
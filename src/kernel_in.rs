@@ -4,10 +4,11 @@ use std::collections::HashMap;
 
 use serde::Deserialize;
 
-pub static PI: f32 = std::f32::consts::PI;
 pub static LAT_FAKT: f64 = 111120.0; // 111100.0  111285; // exactly enough  111120 = 1.852 * 1000.0 * 60 - It is in the OSM wiki: 1′ = 1.852 km * 60s/min * 1000m/km = 111120m
 
+use crate::coverage::PartCoverage;
 use crate::footprint::Footprint;
+use crate::projection::LocalProjection;
 
 #[derive(Default, Clone, Copy, Debug)]
 pub struct GeographicCoordinates {
@@ -16,29 +17,58 @@ pub struct GeographicCoordinates {
 }
 
 impl GeographicCoordinates {
+    // 0,0 is in the Atlantic, not a place with buildings. It is used as "no GPU 0 position set"
+    pub fn is_set(&self) -> bool {
+        self.latitude != 0. || self.longitude != 0.
+    }
+
     /*
      * Rotate lat/lon to reposition the home point onto 0,0.
      * @param {[number, number]} lonLat - The longitute and latitude of a point.
      * @return {[number, number]} x, y in meters
+     * For many points, create the LocalProjection once and use it
      */
 
     pub fn coordinates_to_position(&self, latitude: f64, longitude: f64) -> GroundPosition {
         // If no GPU 0 position is set, return just the GPS position. Used to find the GPU 0 position
-        if self.latitude == 0. {
+        if !self.is_set() {
             return GroundPosition {
                 x: longitude,
                 y: latitude,
             };
         }
+        LocalProjection::new(*self).forward(latitude, longitude)
+    }
 
-        // The closer to a pole, the smaller the tiles size in meters get
-        let lon_fakt = LAT_FAKT * ((latitude / 180. * PI as f64).abs()).cos();
-        // Longitude(Längengrad) West/East factor
-
-        GroundPosition {
-            y: ((latitude - self.latitude) * LAT_FAKT),
-            x: ((longitude - self.longitude) * lon_fakt),
+    // The inverse of coordinates_to_position: x, y in meters back to lat/lon
+    pub fn position_to_coordinates(&self, position: GroundPosition) -> GeographicCoordinates {
+        if !self.is_set() {
+            return GeographicCoordinates {
+                latitude: position.y,
+                longitude: position.x,
+            };
         }
+        LocalProjection::new(*self).inverse(position)
+    }
+
+    // Opens the iD editor at this point
+    pub fn id_editor_url(&self) -> String {
+        format!(
+            "https://www.openstreetmap.org/edit?editor=id#map=19/{:.6}/{:.6}",
+            self.latitude, self.longitude
+        )
+    }
+
+    // Loads the area around this point into a running JOSM. Its remote control must be enabled
+    pub fn josm_url(&self) -> String {
+        let delta = 0.0005; // about 50 meters
+        format!(
+            "http://127.0.0.1:8111/load_and_zoom?left={:.6}&right={:.6}&top={:.6}&bottom={:.6}",
+            self.longitude - delta,
+            self.longitude + delta,
+            self.latitude + delta,
+            self.latitude - delta
+        )
     }
 }
 
@@ -136,6 +166,31 @@ pub enum RoofShape {
     Onion,
 }
 
+// How the walls are dressed, if the optional facade generator is used
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Facade {
+    Windows,   // default: one row of windows per level
+    Glass,     // building:facade=glass or curtain_wall: one glass band per level
+    NoWindows, // window=no or building:facade=blind: only the level lines
+}
+
+// Buildings and parts, not made of walls and a roof
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Structure {
+    Solid,                  // walls and a roof
+    Steps { count: usize }, // building:part=steps, rising against the roof direction
+    RoofOnly,               // building=roof: a roof on pillars, like carports and canopies
+    ThinRoof,               // building:part=roof or min_height at the roof: only a roof slab
+}
+
+// How roof:direction set the roof angle
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoofSnap {
+    NotTagged,                     // by the longest side and roof:orientation
+    Unsnapped,                     // as tagged, no footprint edge was close enough
+    Snapped { tagged_angle: f64 }, // turned to the closest edge normal
+}
+
 /*
  * Extend the area of the OSM object to the given range at last
  * @param {f32} range in meters - the minimum range of the bounding box
@@ -273,15 +328,59 @@ pub struct BuildingOrPart {
     // upper height of the wall, independend of / including the min_height
     pub wall_height: f64,
     pub min_height: f64,
+    // The terrain below the lowest corner of the building, relative to the GPU 0 position.
+    // All heights above are relative to it. 0 without elevation data
+    pub ground_height: f64,
+    // Of a part: how it lies in its building. Parts in no building are Outside. Buildings: Inside
+    pub coverage: PartCoverage,
+    // Nodes or relation members were not loaded, the footprint may be wrong
+    pub incomplete: bool,
+    // building:levels, building:min_level and roof:levels, 0 if not tagged
+    pub levels: f64,
+    pub min_level: f64,
+    pub roof_levels: f64,
+    // by the tagged height and levels or by the building type
+    pub level_height: f64,
+    // building:levels:underground. A negative min_height reaches below the ground
+    pub underground_levels: f64,
+    pub underground: bool,
+    pub facade: Facade,
+    pub structure: Structure,
     pub building_color: RenderColor,
     pub roof_shape: RoofShape,
     pub roof_height: f64,
     pub roof_angle: f64,
+    pub roof_snap: RoofSnap,
     pub roof_color: RenderColor,
 }
 
 pub type BuildingsAndParts = Vec<BuildingOrPart>;
 
+// Flat features around the buildings, to see them in context
+#[derive(Clone, Debug)]
+pub enum GroundShape {
+    Area(Footprint),
+    Line {
+        positions: GroundPositions,
+        width: f64,
+    },
+    Tree {
+        position: GroundPosition,
+        height: f64,
+        crown_diameter: f64,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct GroundFeature {
+    pub id: u64, // OSM id of the way or node
+    pub shape: GroundShape,
+    pub color: RenderColor,
+    pub level: f64, // a little above the ground: roads cover water, water covers landuse
+}
+
+pub type GroundFeatures = Vec<GroundFeature>;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Member {
     #[serde(rename = "type")]
@@ -0,0 +1,212 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Lengths of OSM tags like height=12'6" or roof:height=350 cm, converted to meters ///////////////
+
+// https://wiki.openstreetmap.org/wiki/Map_features/Units
+pub static METERS_PER_FOOT: f64 = 0.3048;
+pub static METERS_PER_INCH: f64 = 0.0254;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthUnit {
+    None, // no unit given, meters are the default
+    Meter,
+    Centimeter,
+    Millimeter,
+    Foot,
+    Inch,
+    FootInch, // 12'6"
+}
+
+impl LengthUnit {
+    pub fn meters(&self) -> f64 {
+        match self {
+            LengthUnit::None | LengthUnit::Meter => 1.,
+            LengthUnit::Centimeter => 0.01,
+            LengthUnit::Millimeter => 0.001,
+            LengthUnit::Foot | LengthUnit::FootInch => METERS_PER_FOOT,
+            LengthUnit::Inch => METERS_PER_INCH,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LengthError {
+    Empty,
+    NotANumber,
+    UnknownUnit,
+    Negative,
+}
+
+impl std::fmt::Display for LengthError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            LengthError::Empty => "empty",
+            LengthError::NotANumber => "not a number",
+            LengthError::UnknownUnit => "unknown unit",
+            LengthError::Negative => "negative",
+        };
+        write!(f, "{text}")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Length {
+    pub meters: f64,
+    pub unit: LengthUnit,
+    // A list "10;12" or a range "10-12" was given. The largest value is used
+    pub ambiguous: bool,
+}
+
+/*
+ * A length value in meters: "12", "12 m", "3,5", "350 cm", "40 ft", "12'6\"", "10;12", "10-12 m".
+ * A unit at one value of a list or range counts for the values without a unit.
+ */
+pub fn parse_length(value: &str) -> Result<Length, LengthError> {
+    parse_any_length(value, false)
+}
+
+// Like parse_length, also below the ground: min_height=-5
+pub fn parse_signed_length(value: &str) -> Result<Length, LengthError> {
+    parse_any_length(value, true)
+}
+
+fn parse_any_length(value: &str, signed: bool) -> Result<Length, LengthError> {
+    let mut lengths = Vec::new();
+    for item in split_list(value) {
+        lengths.push(parse_single_length(item, signed)?);
+    }
+    let unit = lengths
+        .iter()
+        .map(|length| length.unit)
+        .rfind(|unit| *unit != LengthUnit::None)
+        .unwrap_or(LengthUnit::None);
+
+    let mut largest: Option<Length> = None;
+    for mut length in lengths.iter().copied() {
+        if length.unit == LengthUnit::None {
+            length.meters *= unit.meters();
+            length.unit = unit;
+        }
+        if largest.is_none_or(|largest| length.meters > largest.meters) {
+            largest = Some(length);
+        }
+    }
+    let mut largest = largest.ok_or(LengthError::Empty)?;
+    largest.ambiguous = lengths.len() > 1;
+    Ok(largest)
+}
+
+/*
+ * A number of levels, no unit allowed: "3", "2.5", "3,5", "3;4" (the largest)
+ */
+pub fn parse_count(value: &str) -> Result<f64, LengthError> {
+    let mut largest: Option<f64> = None;
+    for item in split_list(value) {
+        let count = parse_number(item, false)?;
+        largest = Some(largest.map_or(count, |largest| largest.max(count)));
+    }
+    largest.ok_or(LengthError::Empty)
+}
+
+// "10;12" and "10-12" in single values. A leading "-" is a sign, not a range
+fn split_list(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    for item in value.split(';') {
+        let item = item.trim();
+        let range = item
+            .char_indices()
+            .skip(1)
+            .find(|(_, char)| *char == '-')
+            .map(|(index, _)| index);
+        match range {
+            Some(index) => {
+                items.push(item[..index].trim());
+                items.push(item[index + 1..].trim());
+            }
+            None => items.push(item),
+        }
+    }
+    items
+}
+
+fn parse_single_length(item: &str, signed: bool) -> Result<Length, LengthError> {
+    // Pairs of a number and a unit: 12'6" => ("12","'") ("6","\"")
+    let mut pairs: Vec<(&str, &str)> = Vec::new();
+    let mut rest = item.trim();
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|char: char| !(char.is_ascii_digit() || ".,+-".contains(char)))
+            .unwrap_or(rest.len());
+        let number = &rest[..number_end];
+        rest = rest[number_end..].trim_start();
+        let unit_end = rest
+            .find(|char: char| char.is_ascii_digit() || char.is_whitespace())
+            .unwrap_or(rest.len());
+        let unit = &rest[..unit_end];
+        rest = rest[unit_end..].trim_start();
+        if number.is_empty() {
+            return Err(LengthError::NotANumber);
+        }
+        pairs.push((number, unit));
+    }
+
+    match pairs.as_slice() {
+        [] => Err(LengthError::Empty),
+        [(number, unit)] => {
+            let unit = parse_unit(unit)?;
+            Ok(Length {
+                meters: parse_number(number, signed)? * unit.meters(),
+                unit,
+                ambiguous: false,
+            })
+        }
+        [(feet, foot), (inches, inch)] => {
+            if parse_unit(foot)? != LengthUnit::Foot || parse_unit(inch)? != LengthUnit::Inch {
+                return Err(LengthError::UnknownUnit);
+            }
+            // -5'6" is 5'6" below the ground
+            let feet = parse_number(feet, signed)?;
+            let inches = parse_number(inches, false)?.copysign(feet);
+            Ok(Length {
+                meters: feet * METERS_PER_FOOT + inches * METERS_PER_INCH,
+                unit: LengthUnit::FootInch,
+                ambiguous: false,
+            })
+        }
+        _ => Err(LengthError::UnknownUnit),
+    }
+}
+
+fn parse_unit(unit: &str) -> Result<LengthUnit, LengthError> {
+    match unit.to_lowercase().as_str() {
+        "" => Ok(LengthUnit::None),
+        "m" | "meter" | "meters" | "metre" | "metres" => Ok(LengthUnit::Meter),
+        "cm" => Ok(LengthUnit::Centimeter),
+        "mm" => Ok(LengthUnit::Millimeter),
+        "'" | "ft" | "foot" | "feet" => Ok(LengthUnit::Foot),
+        "\"" | "''" | "in" | "inch" | "inches" => Ok(LengthUnit::Inch),
+        _ => Err(LengthError::UnknownUnit),
+    }
+}
+
+// "3.5" or "3,5". A comma is only a decimal separator if there is no point
+fn parse_number(number: &str, signed: bool) -> Result<f64, LengthError> {
+    let number = number.trim();
+    if number.is_empty() {
+        return Err(LengthError::Empty);
+    }
+    let number = if !number.contains('.') && number.matches(',').count() == 1 {
+        number.replace(',', ".")
+    } else {
+        number.to_string()
+    };
+    // Only digits, no "inf" or "NaN"
+    if !number.chars().any(|char| char.is_ascii_digit()) {
+        return Err(LengthError::NotANumber);
+    }
+    match number.parse::<f64>() {
+        Ok(value) if !value.is_finite() => Err(LengthError::NotANumber),
+        Ok(value) if value < 0. && !signed => Err(LengthError::Negative),
+        Ok(value) => Ok(value),
+        Err(_) => Err(LengthError::NotANumber),
+    }
+}
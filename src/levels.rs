@@ -0,0 +1,262 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Levels and heights of a building or part ///////////////////////////////////////////////////////
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics};
+use crate::kernel_in::{OsmMap, RoofShape};
+use crate::length::{Length, LengthError, parse_count, parse_length, parse_signed_length};
+use crate::osm2layers::tags_get2;
+
+pub static DEFAULT_LEVEL_HEIGHT: f64 = 3.0;
+// A level height, calculated by height/levels outside of this range, is a tagging conflict
+static MIN_LEVEL_HEIGHT: f64 = 2.0;
+static MAX_LEVEL_HEIGHT: f64 = 10.0;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Heights {
+    pub min_height: f64,
+    pub wall_height: f64,
+    pub roof_height: f64,
+    pub levels: f64,
+    pub min_level: f64,
+    pub roof_levels: f64,
+    pub level_height: f64,
+    pub underground_levels: f64,
+    pub underground: bool, // the whole volume is below the ground
+}
+
+// Typical height of one level, by the value of building=*. Parts get the value of their building
+pub fn level_height_by_building_type(building: Option<&String>) -> f64 {
+    let Some(building) = building else {
+        return DEFAULT_LEVEL_HEIGHT;
+    };
+    match building.as_str() {
+        "house" | "detached" | "semidetached_house" | "terrace" | "bungalow" | "cabin" | "farm"
+        | "hut" => 2.8,
+        "residential" | "apartments" | "dormitory" | "hotel" => 3.0,
+        "office" | "commercial" | "retail" | "school" | "university" | "college"
+        | "kindergarten" | "hospital" | "public" | "civic" | "government" | "townhall" => 3.5,
+        "industrial" | "warehouse" | "factory" | "manufacture" | "hangar" | "supermarket"
+        | "sports_hall" | "train_station" | "transportation" => 5.0,
+        "church" | "cathedral" | "chapel" | "mosque" | "temple" | "synagogue" | "shrine"
+        | "religious" => 6.0,
+        "garage" | "garages" | "shed" | "carport" | "roof" => 2.5,
+        _ => DEFAULT_LEVEL_HEIGHT,
+    }
+}
+
+// location=underground, or a negative layer if the location does not tell otherwise
+// https://wiki.openstreetmap.org/wiki/Key:location
+pub fn is_underground(tags: &OsmMap) -> bool {
+    match tags.get("location").map(|location| location.as_str()) {
+        Some("underground") => true,
+        Some(_) => false,
+        None => tags
+            .get("layer")
+            .and_then(|layer| layer.trim().parse::<i32>().ok())
+            .is_some_and(|layer| layer < 0),
+    }
+}
+
+// A length tag in meters, 0 if missing or bad
+fn length_value(value: Option<&String>, id: u64, diagnostics: &mut Diagnostics) -> f64 {
+    parsed_value(value, parse_length, id, diagnostics)
+}
+
+// Like length_value, may be below the ground
+fn signed_length_value(value: Option<&String>, id: u64, diagnostics: &mut Diagnostics) -> f64 {
+    parsed_value(value, parse_signed_length, id, diagnostics)
+}
+
+fn parsed_value(
+    value: Option<&String>,
+    parse: fn(&str) -> Result<Length, LengthError>,
+    id: u64,
+    diagnostics: &mut Diagnostics,
+) -> f64 {
+    let Some(value) = value else {
+        return 0.;
+    };
+    match parse(value) {
+        Ok(length) => length.meters,
+        Err(error) => {
+            diagnostics.push(Diagnostic {
+                id,
+                kind: DiagnosticKind::BadLength(error),
+                value: value.clone(),
+            });
+            0.
+        }
+    }
+}
+
+// A levels tag, 0 if missing or bad
+fn count_value(value: Option<&String>, id: u64, diagnostics: &mut Diagnostics) -> f64 {
+    let Some(value) = value else {
+        return 0.;
+    };
+    match parse_count(value) {
+        Ok(count) => count,
+        Err(error) => {
+            diagnostics.push(Diagnostic {
+                id,
+                kind: DiagnosticKind::BadLength(error),
+                value: value.clone(),
+            });
+            0.
+        }
+    }
+}
+
+fn height_conflict(id: u64, diagnostics: &mut Diagnostics, value: String) {
+    diagnostics.push(Diagnostic {
+        id,
+        kind: DiagnosticKind::HeightConflict,
+        value,
+    });
+}
+
+/*
+ * All heights of a building or part, by the tagged heights, levels and the level height model
+ * https://wiki.openstreetmap.org/wiki/Simple_3D_Buildings#Height_and_levels
+ * Levels are counted from the ground. building:min_level levels are skipped, roof:levels are inside the roof.
+ * @param building_type - building=* of the building or of the building around the part
+ * @param default_roof_height - height of the roof shape, if neither roof:height nor roof:levels are given
+ * @param default_wall_height - if neither a height nor levels are given, by the interpretation profile
+ */
+pub fn process_heights(
+    tags: &OsmMap,
+    building_type: Option<&String>,
+    roof_shape: RoofShape,
+    default_roof_height: f64,
+    default_wall_height: f64,
+    id: u64,
+    diagnostics: &mut Diagnostics,
+) -> Heights {
+    let levels = count_value(tags.get("building:levels"), id, diagnostics);
+    let min_level = count_value(tags.get("building:min_level"), id, diagnostics);
+    let roof_levels = count_value(tags.get("roof:levels"), id, diagnostics);
+    let tagged_min_height = signed_length_value(tags.get("min_height"), id, diagnostics);
+    let tagged_roof_height = length_value(tags.get("roof:height"), id, diagnostics);
+    let tagged_height = length_value(
+        tags_get2(tags, "building:height", "height"),
+        id,
+        diagnostics,
+    );
+    let underground_levels = count_value(tags.get("building:levels:underground"), id, diagnostics);
+
+    // roof:levels without a roof shape: The levels are not inside a roof but are upper wall levels
+    let has_roof = !matches!(roof_shape, RoofShape::Flat | RoofShape::None);
+    let wall_roof_levels = if has_roof { 0. } else { roof_levels };
+
+    // ** Level height: by the building type or by this building's own height and levels **
+    let mut level_height = level_height_by_building_type(building_type);
+    if tagged_height > 0. && levels > 0. {
+        let calculated = if tagged_roof_height > 0. {
+            (tagged_height - tagged_roof_height) / (levels + wall_roof_levels)
+        } else {
+            tagged_height / (levels + roof_levels)
+        };
+        if (MIN_LEVEL_HEIGHT..=MAX_LEVEL_HEIGHT).contains(&calculated) {
+            level_height = calculated;
+        } else {
+            height_conflict(
+                id,
+                diagnostics,
+                format!(
+                    "height {tagged_height} does not fit {levels} levels ({calculated:.1}m per level)"
+                ),
+            );
+        }
+    }
+
+    // ** Completely below the ground: from the depth up to the ground, no roof **
+    if is_underground(tags) {
+        let depth = if underground_levels > 0. {
+            underground_levels * level_height
+        } else if levels > 0. {
+            levels * level_height
+        } else if tagged_height > 0. {
+            tagged_height
+        } else {
+            level_height
+        };
+        return Heights {
+            min_height: -depth,
+            wall_height: 0.,
+            roof_height: 0.,
+            levels: 0.,
+            min_level: 0.,
+            roof_levels: 0.,
+            level_height,
+            underground_levels: (depth / level_height).round(),
+            underground: true,
+        };
+    }
+
+    // ** Min height **
+    let mut min_height = tagged_min_height;
+    if min_level > 0. {
+        let min_level_height = min_level * level_height;
+        if tagged_min_height == 0. {
+            min_height = min_level_height;
+        } else if (tagged_min_height - min_level_height).abs() > level_height {
+            height_conflict(
+                id,
+                diagnostics,
+                format!(
+                    "min_height {tagged_min_height} does not fit building:min_level {min_level}"
+                ),
+            );
+        }
+    }
+    // Basement levels extend the walls below the ground
+    if underground_levels > 0. && min_height == 0. {
+        min_height = -underground_levels * level_height;
+    }
+    if min_level > 0. && levels > 0. && min_level >= levels {
+        height_conflict(
+            id,
+            diagnostics,
+            format!("building:min_level {min_level} is not below building:levels {levels}"),
+        );
+    }
+
+    // ** Roof height **
+    let roof_height = if tagged_roof_height > 0. {
+        tagged_roof_height
+    } else if has_roof && roof_levels > 0. {
+        roof_levels * level_height
+    } else {
+        default_roof_height
+    };
+
+    // ** Building height **
+    let mut building_height = tagged_height;
+    if building_height == 0. && levels > 0. {
+        building_height = (levels + wall_roof_levels) * level_height + roof_height;
+    }
+    if building_height == 0. {
+        building_height = default_wall_height.max(min_height + level_height + roof_height);
+    }
+    let wall_height = building_height - roof_height;
+    // Equal is fine: a roof only part
+    if wall_height < min_height {
+        height_conflict(
+            id,
+            diagnostics,
+            format!("min_height {min_height} is not below the wall height {wall_height}"),
+        );
+    }
+
+    Heights {
+        min_height,
+        wall_height,
+        roof_height,
+        levels: levels + wall_roof_levels,
+        min_level,
+        roof_levels: roof_levels - wall_roof_levels,
+        level_height,
+        underground_levels,
+        underground: false,
+    }
+}
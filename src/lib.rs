@@ -10,6 +10,22 @@ mod input_osm_json;
 //     pub use input_osm_json::JsonData;
 #[cfg(feature = "json")]
 pub use input_osm_json::*;
+#[cfg(feature = "json")]
+mod response_cache;
+#[cfg(feature = "json")]
+pub use response_cache::*;
+#[cfg(feature = "json")]
+mod history;
+#[cfg(feature = "json")]
+pub use history::*;
+#[cfg(feature = "json")]
+mod osm_change;
+#[cfg(feature = "json")]
+pub use osm_change::*;
+#[cfg(feature = "json")]
+mod editing;
+#[cfg(feature = "json")]
+pub use editing::*;
 
 #[cfg(feature = "xmllib")]
 mod input_osm_lib;
@@ -26,19 +42,54 @@ mod osm2layers;
 pub use osm2layers::*;
 mod footprint;
 pub use footprint::*;
+mod length;
+pub use length::*;
+mod levels;
+pub use levels::*;
+mod colors;
+pub use colors::*;
+mod diagnostics;
+pub use diagnostics::*;
+mod profile;
+pub use profile::*;
+mod coverage;
+pub use coverage::*;
+mod projection;
+pub use projection::*;
+mod elevation;
+pub use elevation::*;
+mod ground;
+pub use ground::*;
 
 // Interfaces from the input modules to renderer
 mod kernel_in;
 pub use kernel_in::BoundingBox;
+pub use kernel_in::BuildingOrPart;
+pub use kernel_in::BuildingsAndParts;
+pub use kernel_in::Facade;
 pub use kernel_in::GeographicCoordinates;
+pub use kernel_in::GroundFeature;
+pub use kernel_in::GroundFeatures;
 pub use kernel_in::GroundPosition;
+pub use kernel_in::GroundShape;
 pub use kernel_in::LAT_FAKT;
+pub use kernel_in::RoofSnap;
+pub use kernel_in::Structure;
 pub use kernel_in::center_as_geographic_coordinates;
 pub use kernel_in::max_range; // todo: hide in lib by fn
 
 // 3D and 2D rendere are possible
 mod symbolic_3d;
 pub use symbolic_3d::*;
+// Optional windows and level lines of the 3D renderer
+mod facade;
+pub use facade::*;
+// The 2D renderer: a plan view as SVG
+mod symbolic_2d;
+pub use symbolic_2d::*;
+// Checks of the rendered meshes, for tests and the CLI
+mod mesh_check;
+pub use mesh_check::*;
 
 // Interface from an rederer to an output
 mod kernel_out;
@@ -46,6 +97,10 @@ pub use kernel_out::*;
 
 // Variouns outputs are possible (UI, create a GLB file
 
+// PNG snapshots, rendered by the CPU, without a window
+mod snapshot;
+pub use snapshot::*;
+
 // BEVY
 #[cfg(feature = "bevy")]
 mod bevy_ui;
@@ -56,9 +111,13 @@ pub use bevy_ui::*;
 #[cfg(feature = "bevy")]
 mod bevy_control;
 #[cfg(feature = "bevy")]
+pub use bevy_control::ControlNoCamera;
+#[cfg(feature = "bevy")]
 pub use bevy_control::ControlValues;
 #[cfg(feature = "bevy")]
 pub use bevy_control::ControlWithCamera;
+#[cfg(feature = "bevy")]
+pub use bevy_control::KeyBindings;
 
 // REND3
 #[cfg(feature = "rend3")]
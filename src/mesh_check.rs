@@ -0,0 +1,327 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Mesh checks: open or shared edges, winding, degenerate and z-fighting triangles ////////////////
+
+/*
+ * The corners of the meshes are not reused, to get hard edges. So the triangles are
+ * connected by the positions of their corners, welded at a millimeter.
+ * A closed mesh has each edge in two triangles, running it in opposite directions.
+ */
+
+use std::collections::HashMap;
+
+use crate::kernel_out::{OsmMeshAttributes, RenderPosition};
+
+static WELD: f64 = 0.001; // meters
+static MIN_AREA: f64 = 1e-6; // square meters
+static PLANE_STEP: f64 = 0.01; // of the normal and meters of the plane offset
+static MIN_OVERLAP: f64 = 0.01; // square meters
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MeshProblemKind {
+    IndexOutOfRange,     // no vertex for an index
+    Degenerate,          // a triangle without area
+    OpenEdge,            // in one triangle only, the mesh is not watertight
+    NonManifoldEdge,     // in more than two triangles
+    InconsistentWinding, // two triangles run their edge in the same direction, one is inverted
+    ZFighting,           // two triangles overlap in the same plane and flicker
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct MeshProblem {
+    pub kind: MeshProblemKind,
+    pub mesh: usize,              // index in the checked meshes
+    pub triangle: usize,          // index of the triangle, the index of its first corner / 3
+    pub position: RenderPosition, // where to look: a corner or the middle of the edge
+}
+
+pub type MeshProblems = Vec<MeshProblem>;
+
+// Options of the mesh check
+#[derive(Clone, Copy, Debug)]
+pub struct MeshCheckOptions {
+    // The walls stand on the ground without a floor, their bottom edges are open
+    pub open_bottoms: bool,
+}
+
+impl Default for MeshCheckOptions {
+    fn default() -> Self {
+        Self { open_bottoms: true }
+    }
+}
+
+impl std::fmt::Display for MeshProblemKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MeshProblemKind::IndexOutOfRange => "Index out of range",
+            MeshProblemKind::Degenerate => "Degenerate triangle",
+            MeshProblemKind::OpenEdge => "Open edge",
+            MeshProblemKind::NonManifoldEdge => "Non-manifold edge",
+            MeshProblemKind::InconsistentWinding => "Inconsistent winding",
+            MeshProblemKind::ZFighting => "Z-fighting",
+        };
+        write!(f, "{name}")
+    }
+}
+
+impl std::fmt::Display for MeshProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let [x, y, z] = self.position;
+        write!(
+            f,
+            "Mesh {} triangle {}: {} at {x:.2} {y:.2} {z:.2}",
+            self.mesh, self.triangle, self.kind
+        )
+    }
+}
+
+pub fn check_meshes(osm_meshes: &[OsmMeshAttributes]) -> MeshProblems {
+    check_meshes_with_options(osm_meshes, &MeshCheckOptions::default())
+}
+
+pub fn check_meshes_with_options(
+    osm_meshes: &[OsmMeshAttributes],
+    options: &MeshCheckOptions,
+) -> MeshProblems {
+    let mut problems = Vec::new();
+    for (mesh_index, mesh) in osm_meshes.iter().enumerate() {
+        for mut problem in check_mesh(mesh, options) {
+            problem.mesh = mesh_index;
+            problems.push(problem);
+        }
+    }
+    problems
+}
+
+// The number of problems of each kind, like "Open edge: 4"
+pub fn mesh_problems_summary(problems: &MeshProblems) -> String {
+    let mut counts = std::collections::BTreeMap::new();
+    for problem in problems {
+        *counts.entry(problem.kind).or_insert(0) += 1;
+    }
+    counts
+        .iter()
+        .map(|(kind, count)| format!("{kind}: {count}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn check_mesh(mesh: &OsmMeshAttributes, options: &MeshCheckOptions) -> MeshProblems {
+    let mut problems = Vec::new();
+    let problem = |kind, triangle, position| MeshProblem {
+        kind,
+        mesh: 0,
+        triangle,
+        position,
+    };
+
+    // The valid triangles, as f64 positions
+    let mut triangles: Vec<(usize, [[f64; 3]; 3])> = Vec::new();
+    for (triangle_index, triangle) in mesh.indices_to_vertices.chunks_exact(3).enumerate() {
+        let found: Vec<Option<&RenderPosition>> = triangle
+            .iter()
+            .map(|index| mesh.vertices_positions.get(*index as usize))
+            .collect();
+        let [Some(a), Some(b), Some(c)] = found[..] else {
+            // At a corner, found or not
+            let position = found.iter().flatten().next().copied().copied();
+            problems.push(problem(
+                MeshProblemKind::IndexOutOfRange,
+                triangle_index,
+                position.unwrap_or_default(),
+            ));
+            continue;
+        };
+        let corners = [a, b, c].map(|corner| corner.map(|value| value as f64));
+        if length(normal_of(&corners)) / 2. < MIN_AREA {
+            problems.push(problem(MeshProblemKind::Degenerate, triangle_index, *a));
+            continue;
+        }
+        triangles.push((triangle_index, corners));
+    }
+
+    // Edges by their welded ends: the triangles and if they run it from the lower end
+    type Weld = [i64; 3];
+    let weld = |position: &[f64; 3]| position.map(|value| (value / WELD).round() as i64);
+    let mut edges: HashMap<(Weld, Weld), Vec<(usize, bool)>> = HashMap::new();
+    for (list_index, (_, corners)) in triangles.iter().enumerate() {
+        for corner in 0..3 {
+            let from = weld(&corners[corner]);
+            let to = weld(&corners[(corner + 1) % 3]);
+            let key = if from < to { (from, to) } else { (to, from) };
+            edges.entry(key).or_default().push((list_index, from < to));
+        }
+    }
+    let mut edge_problems = Vec::new();
+    for ((from, to), users) in &edges {
+        let middle = [0, 1, 2].map(|axis| (from[axis] as f64 + to[axis] as f64) * WELD / 2.);
+        let (list_index, forward) = users[0];
+        let (triangle_index, corners) = &triangles[list_index];
+        let kind = match users.len() {
+            1 if options.open_bottoms && is_wall_bottom(corners, middle[1]) => continue,
+            1 => MeshProblemKind::OpenEdge,
+            2 if users[1].1 == forward => MeshProblemKind::InconsistentWinding,
+            2 => continue,
+            _ => MeshProblemKind::NonManifoldEdge,
+        };
+        edge_problems.push(problem(kind, *triangle_index, to_render(middle)));
+    }
+    // The hash map has no order
+    edge_problems.sort_by_key(|problem| (problem.triangle, problem.kind));
+    problems.extend(edge_problems);
+
+    problems.extend(
+        z_fighting(&triangles)
+            .into_iter()
+            .map(|(triangle_index, position)| {
+                problem(MeshProblemKind::ZFighting, triangle_index, position)
+            }),
+    );
+    problems
+}
+
+// A vertical triangle with its lowest edge at that height
+fn is_wall_bottom(corners: &[[f64; 3]; 3], height: f64) -> bool {
+    let normal = normal_of(corners);
+    let lowest = corners
+        .iter()
+        .map(|corner| corner[1])
+        .fold(f64::INFINITY, f64::min);
+    normal[1].abs() < length(normal) * PLANE_STEP && (height - lowest).abs() < WELD
+}
+
+/*
+ * Triangles in the same plane, facing the same side, are grouped by their rounded plane.
+ * In a group, each pair is tested for a common area, seen along the normal.
+ */
+fn z_fighting(triangles: &[(usize, [[f64; 3]; 3])]) -> Vec<(usize, RenderPosition)> {
+    let mut planes: HashMap<[i64; 4], Vec<usize>> = HashMap::new();
+    for (list_index, (_, corners)) in triangles.iter().enumerate() {
+        let normal = normal_of(corners);
+        let normal = normal.map(|value| value / length(normal));
+        let offset = dot(normal, corners[0]);
+        let step = |value: f64| (value / PLANE_STEP).round() as i64;
+        let key = [
+            step(normal[0]),
+            step(normal[1]),
+            step(normal[2]),
+            step(offset),
+        ];
+        planes.entry(key).or_default().push(list_index);
+    }
+
+    let mut fighting = Vec::new();
+    for group in planes.values() {
+        for (number, first) in group.iter().enumerate() {
+            for second in &group[number + 1..] {
+                let (a, b) = (&triangles[*first], &triangles[*second]);
+                if overlap_area(&a.1, &b.1) > MIN_OVERLAP {
+                    let later = if a.0 > b.0 { a } else { b };
+                    let center = [0, 1, 2]
+                        .map(|axis| (later.1[0][axis] + later.1[1][axis] + later.1[2][axis]) / 3.);
+                    fighting.push((later.0, to_render(center)));
+                }
+            }
+        }
+    }
+    fighting.sort_by_key(|(triangle_index, _)| *triangle_index);
+    fighting
+}
+
+// The common area of two triangles in about the same plane, projected along its main axis
+fn overlap_area(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> f64 {
+    let normal = normal_of(a);
+    let main = (0..3)
+        .max_by(|x, y| normal[*x].abs().total_cmp(&normal[*y].abs()))
+        .unwrap_or(1);
+    let (u, v) = ((main + 1) % 3, (main + 2) % 3);
+    let flat = |corners: &[[f64; 3]; 3]| -> Vec<[f64; 2]> {
+        let mut polygon: Vec<[f64; 2]> = corners.iter().map(|c| [c[u], c[v]]).collect();
+        if signed_area(&polygon) < 0. {
+            polygon.reverse();
+        }
+        polygon
+    };
+    let (subject, clip) = (flat(a), flat(b));
+    // Bounding boxes first, most pairs are apart
+    for axis in 0..2 {
+        let min = |polygon: &[[f64; 2]]| {
+            polygon
+                .iter()
+                .map(|p| p[axis])
+                .fold(f64::INFINITY, f64::min)
+        };
+        let max = |polygon: &[[f64; 2]]| {
+            polygon
+                .iter()
+                .map(|p| p[axis])
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+        if min(&subject) >= max(&clip) || min(&clip) >= max(&subject) {
+            return 0.;
+        }
+    }
+
+    // Sutherland–Hodgman, the clip triangle is convex and counter clockwise
+    let mut polygon = subject;
+    for edge in 0..3 {
+        let (from, to) = (clip[edge], clip[(edge + 1) % 3]);
+        let side = |p: [f64; 2]| {
+            (to[0] - from[0]) * (p[1] - from[1]) - (to[1] - from[1]) * (p[0] - from[0])
+        };
+        let mut clipped = Vec::new();
+        for index in 0..polygon.len() {
+            let current = polygon[index];
+            let next = polygon[(index + 1) % polygon.len()];
+            let (side_current, side_next) = (side(current), side(next));
+            if side_current >= 0. {
+                clipped.push(current);
+            }
+            if (side_current >= 0.) != (side_next >= 0.) {
+                let share = side_current / (side_current - side_next);
+                clipped.push([
+                    current[0] + (next[0] - current[0]) * share,
+                    current[1] + (next[1] - current[1]) * share,
+                ]);
+            }
+        }
+        polygon = clipped;
+        if polygon.len() < 3 {
+            return 0.;
+        }
+    }
+    // The projection shrinks the area by the main axis part of the normal
+    signed_area(&polygon) * length(normal) / normal[main].abs()
+}
+
+fn signed_area(polygon: &[[f64; 2]]) -> f64 {
+    let mut area = 0.;
+    for index in 0..polygon.len() {
+        let (a, b) = (polygon[index], polygon[(index + 1) % polygon.len()]);
+        area += a[0] * b[1] - b[0] * a[1];
+    }
+    area / 2.
+}
+
+// Not normalized, its length is the double area
+fn normal_of(corners: &[[f64; 3]; 3]) -> [f64; 3] {
+    let [a, b, c] = corners;
+    let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    [
+        ab[1] * ac[2] - ab[2] * ac[1],
+        ab[2] * ac[0] - ab[0] * ac[2],
+        ab[0] * ac[1] - ab[1] * ac[0],
+    ]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn length(a: [f64; 3]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+fn to_render(position: [f64; 3]) -> RenderPosition {
+    position.map(|value| value as f32)
+}
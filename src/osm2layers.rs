@@ -3,18 +3,31 @@
 /////////////////////////////////////////
 
 //use bevy::prelude::info;
-use csscolorparser::parse;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
 
 // geo primitives
-use geo::{Contains, HasDimensions};
+use geo::{Area, BooleanOps, BoundingRect, HasDimensions, Intersects, LineString};
+use geo::{MultiPolygon, Polygon, unary_union};
 
+use crate::colors::Palette;
+use crate::coverage::{PartCoverage, classify_part, remove_slivers};
+use crate::diagnostics::{Diagnostic, DiagnosticKind, Diagnostics};
+use crate::elevation::Elevation;
 use crate::footprint::{Footprint, Orientation};
+use crate::ground::{CROWN_COLOR, classify_area, classify_line, tree_size};
 use crate::kernel_in::Members;
 use crate::kernel_in::{
-    BuildingOrPart, BuildingsAndParts, GeographicCoordinates, GroundPosition, GroundPositions,
-    OsmMap, RenderColor, RoofShape,
+    BuildingOrPart, BuildingsAndParts, Facade, GeographicCoordinates, GroundFeature,
+    GroundFeatures, GroundPosition, GroundPositions, GroundShape, OsmMap, RenderColor, RoofShape,
+    RoofSnap, Structure,
 };
+use crate::length::parse_length;
+use crate::levels::{Heights, process_heights};
+use crate::profile::{
+    BuildingRemains, InterpretationProfile, InterpretationRules, RoofColorDefault,
+};
+use crate::projection::LocalProjection;
 
 // This constands may come from a (3D-)render shema
 pub static DEFAULT_WALL_COLOR: RenderColor = [0.7, 0.7, 0.7, 1.0]; // "grey" = RenderColor = [0.5, 0.5, 0.5, 1.0];
@@ -24,6 +37,13 @@ pub static DEFAULT_ROOF_HEIGHT: f64 = 2.0;
 pub static DEFAULT_MIN_HEIGHT: f64 = 2.0;
 pub static DEFAULT_BAD_COLOR: [f32; 4] = [98. / 255., 203. / 255., 232. / 255., 1.]; // Electric Blue
 pub static COMPLEX_MIN_NODES: usize = 6 + 1; // +1 because first=last
+pub static STEP_HEIGHT: f64 = 0.17;
+pub static MAX_STEPS: usize = 40;
+pub static DEFAULT_PASSAGE_HEIGHT: f64 = 3.5;
+pub static THIN_ROOF_MAX_WALL: f64 = 0.5; // min_height this close to the wall height is a thin roof
+// roof:direction is snapped to a footprint edge within this degrees. Compass values are 22.5 degrees apart
+pub static COMPASS_SNAP_TOLERANCE: f64 = 11.25;
+pub static ANGLE_SNAP_TOLERANCE: f64 = 5.;
 
 #[derive(PartialEq)]
 enum OuterState {
@@ -44,91 +64,98 @@ fn circle_limit(angle: f64) -> f64 {
     }
 }
 
-fn parse_color(color: Option<&String>, default: RenderColor, id: u64) -> RenderColor {
-    // https://docs.rs/csscolorparser/latest/csscolorparser/
-    // Bevy pbr color needs f32, The parse has no .as_f32}
-    if color.is_none() {
-        return default;
-    }
-
-    let mut color_string = color.unwrap().as_str();
-    //println!("colour: {} ", color_string);
-
-    const LIGHT: &str = "light";
-    const DARK: &str = "dark";
-    let mut enlight: f32 = 1.0;
-    if color_string.starts_with(LIGHT) {
-        color_string = color_string.get(LIGHT.len()..).unwrap();
-        enlight = 1.3;
-    }
-    if color_string.starts_with(DARK) {
-        color_string = color_string.get(DARK.len()..).unwrap();
-        enlight = 1. / 1.3;
-    }
-    if color_string.starts_with('_') {
-        color_string = color_string.get(1..).unwrap();
-    }
-
-    let color_or_error = parse(color_string); // <<<======= parse color =========
-
-    if color_or_error.is_ok() {
-        let color_scc = color_or_error.unwrap();
-        return [
-            color_scc.r as f32 * enlight,
-            color_scc.g as f32 * enlight,
-            color_scc.b as f32 * enlight,
-            color_scc.a as f32 * enlight,
-        ];
-    }
-
-    match color_string {
-        // yellow-brown
-        "metal" => color_to_f32(70, 71, 62),
-        "sandstone" => color_to_f32(191, 166, 116),
-        "slate" => color_to_f32(112, 128, 144),
-        "concrete" => color_to_f32(196, 182, 166),
-        "stone" => color_to_f32(200, 200, 200),
-        "brick" => color_to_f32(255, 128, 128),
-        "cream" => color_to_f32(255, 253, 208),
-        "roof_tiles" => color_to_f32(186, 86, 37),
-        "glass" => color_to_f32(150, 150, 220), // #light grey wiht a bit blue
-        "wood" => color_to_f32(145, 106, 47),
-        "copper" => color_to_f32(98, 190, 119), // Verdigris (Grünspahn) instead of copper = 183 119 41
-
-        _ => {
-            println!(
-                "OSM id {id}: Bad color: {}",
-                color_string,
-                //color_or_error.err()
-            );
-            DEFAULT_BAD_COLOR
-        }
-    }
+// Lenient: 0 for a missing or bad value
+pub(crate) fn parse_height(height_option: Option<&String>) -> f64 {
+    height_option
+        .and_then(|height| parse_length(height).ok())
+        .map_or(0., |length| length.meters)
 }
 
-fn color_to_f32(r: u8, g: u8, b: u8) -> RenderColor {
-    [r as f32 / 255., g as f32 / 255., b as f32 / 255., 1.]
+// https://wiki.openstreetmap.org/wiki/Key:window   building:facade is used by some 3D renderers
+fn parse_facade(tags: &OsmMap) -> Facade {
+    if let Some(facade) = tags.get("building:facade") {
+        match facade.as_str() {
+            "glass" | "curtain_wall" => return Facade::Glass,
+            "blind" | "no" | "none" => return Facade::NoWindows,
+            _ => (),
+        }
+    }
+    if tags_get2(tags, "window", "windows").is_some_and(|window| window == "no") {
+        return Facade::NoWindows;
+    }
+    Facade::Windows
 }
 
-fn parse_height(height_option: Option<&String>) -> f64 {
-    if height_option.is_none() {
-        return 0.;
+// Steps and roof only buildings. The heights are needed to count the steps and to find thin roofs
+fn parse_structure(tags: &OsmMap, min_height: f64, wall_height: f64) -> Structure {
+    let height = wall_height - min_height;
+    let is = |key: &str, value: &str| tags.get(key).is_some_and(|tag| tag == value);
+    if is("building:part", "steps") || is("building", "steps") {
+        let count = match tags
+            .get("step_count")
+            .and_then(|count| count.parse::<usize>().ok())
+        {
+            Some(count) => count,
+            None => (height / STEP_HEIGHT).round() as usize,
+        };
+        return Structure::Steps {
+            count: count.clamp(2, MAX_STEPS),
+        };
     }
-
-    let mut height = height_option.unwrap().clone();
-
-    if height.ends_with("m") {
-        height = height.strip_suffix("m").unwrap().into();
+    if is("building", "roof") {
+        return Structure::RoofOnly;
     }
+    // The F4map thin roof trick: a roof, floating on top of other parts
+    if is("building:part", "roof") || (min_height > 0. && height.abs() < THIN_ROOF_MAX_WALL) {
+        return Structure::ThinRoof;
+    }
+    Structure::Solid
+}
 
-    match height.as_str().trim().parse() {
-        Ok(height) => height,
-
-        Err(error) => {
-            println!("Error! parse_height: {} for:{}:", error, height);
-            0.
+// A passage area along the line, as wide as tagged or typical for the highway
+fn passage_polygon(line: &OsmLine) -> MultiPolygon {
+    let tags = line.tags.as_ref();
+    let width = match tags.and_then(|tags| tags.get("width")) {
+        Some(width) => parse_height(Some(width)),
+        None => match tags
+            .and_then(|tags| tags.get("highway"))
+            .map(|h| h.as_str())
+        {
+            Some("footway" | "path" | "cycleway" | "steps" | "pedestrian") => 2.5,
+            Some("service" | "track") => 3.5,
+            _ => 5.0,
+        },
+    };
+    let half = width.max(1.) / 2.;
+
+    let mut segments = Vec::new();
+    let pieces = line.pieces();
+    for pair in pieces.iter().flat_map(|piece| piece.windows(2)) {
+        let along = pair[1] - pair[0];
+        let length = (along.x * along.x + along.y * along.y).sqrt();
+        if length == 0. {
+            continue;
         }
+        // Extended by half the width, to close the joints
+        let along = along / length * half;
+        let across = GroundPosition {
+            x: along.y,
+            y: -along.x,
+        };
+        let start = pair[0] - along;
+        let end = pair[1] + along;
+        segments.push(Polygon::new(
+            LineString::new(vec![
+                start - across,
+                end - across,
+                end + across,
+                start + across,
+                start - across,
+            ]),
+            Vec::new(),
+        ));
     }
+    unary_union(&segments)
 }
 
 pub fn tags_get_yes<'a>(tags: &'a OsmMap, searched: &str) -> Option<&'a String> {
@@ -139,7 +166,7 @@ pub fn tags_get_yes<'a>(tags: &'a OsmMap, searched: &str) -> Option<&'a String>
     }
 }
 
-fn tags_get2<'a>(tags: &'a OsmMap, option1: &str, option2: &str) -> Option<&'a String> {
+pub(crate) fn tags_get2<'a>(tags: &'a OsmMap, option1: &str, option2: &str) -> Option<&'a String> {
     if let Some(tag) = tags.get(option1) {
         Some(tag)
     } else {
@@ -168,6 +195,8 @@ struct OsmLine {
     id: u64,
     positions: GroundPositions,
     tags: Option<OsmMap>,
+    // Where nodes were not loaded: the line is cut there, instead of going straight over them
+    gaps: Vec<usize>,
 }
 
 impl Default for OsmLine {
@@ -182,7 +211,21 @@ impl OsmLine {
             id,
             positions: Vec::new(),
             tags: None,
+            gaps: Vec::new(),
+        }
+    }
+
+    // The loaded pieces of the line, between the gaps
+    fn pieces(&self) -> Vec<GroundPositions> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        for end in self.gaps.iter().copied().chain([self.positions.len()]) {
+            if end - start >= 2 {
+                pieces.push(self.positions[start..end].to_vec());
+            }
+            start = end;
         }
+        pieces
     }
 }
 
@@ -190,6 +233,7 @@ impl OsmLine {
 
 pub struct OsmNode {
     pub position: GroundPosition,
+    pub tags: Option<OsmMap>, // only for nodes of interest, like trees
 }
 
 #[derive(Debug, Clone)]
@@ -197,6 +241,7 @@ pub struct OsmArea {
     pub _id: u64,
     pub footprint: Footprint,
     pub tags: Option<OsmMap>,
+    pub incomplete: bool, // nodes or members were not loaded, the footprint is not as mapped
 }
 
 #[derive(Debug)]
@@ -206,8 +251,23 @@ pub struct OsmRelation {
     pub tags: Option<OsmMap>,
 }
 
+// Referenced, but not in the loaded data, like nodes or relation members outside of the bbox.
+// The input module may load them and scan again
+#[derive(Clone, Debug, Default)]
+pub struct MissingElements {
+    pub nodes: BTreeSet<u64>,
+    pub ways: BTreeSet<u64>,
+}
+
+impl MissingElements {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.ways.is_empty()
+    }
+}
+
 pub struct Osm2Layer {
-    gpu_ground_null_coordinates: GeographicCoordinates,
+    // None: no GPU 0 position set, the nodes keep lat/lon. Used to find the GPU 0 position
+    projection: Option<LocalProjection>,
     nodes_map: HashMap<u64, OsmNode>,
     areas_map: HashMap<u64, OsmArea>,
     lines_map: HashMap<u64, OsmLine>,
@@ -215,10 +275,19 @@ pub struct Osm2Layer {
     parts: Vec<u64>,
     relations: Vec<OsmRelation>,
     outer_state: OuterState,
+    relation_incomplete: bool,
     first_outer_id_: u64,
     buildings_or_parts: BuildingsAndParts,
     show_only: u64,
     way_only: u64,
+    palette: Palette,
+    diagnostics: Diagnostics,
+    rules: InterpretationRules,
+    elevation: Option<Arc<Elevation>>,
+    ground_features: GroundFeatures,
+    missing: MissingElements,
+    // Of each way, the nodes not loaded. Only those of buildings and parts are missing
+    missing_nodes: HashMap<u64, Vec<u64>>,
 }
 
 impl Osm2Layer {
@@ -228,42 +297,108 @@ impl Osm2Layer {
         way_only: u64,
     ) -> Self {
         Self {
-            gpu_ground_null_coordinates,
+            projection: gpu_ground_null_coordinates
+                .is_set()
+                .then(|| LocalProjection::new(gpu_ground_null_coordinates)),
             nodes_map: HashMap::new(),
             areas_map: HashMap::new(),
             lines_map: HashMap::new(),
             buildings: Vec::new(),
             parts: Vec::new(),
             outer_state: OuterState::New,
+            relation_incomplete: false,
             first_outer_id_: 0,
             relations: Vec::new(),
             buildings_or_parts: Vec::new(),
             show_only,
             way_only,
+            palette: Palette::default(),
+            diagnostics: Vec::new(),
+            rules: InterpretationProfile::default().rules(),
+            elevation: None,
+            ground_features: Vec::new(),
+            missing: MissingElements::default(),
+            missing_nodes: HashMap::new(),
         }
     }
 
+    pub fn set_profile(&mut self, profile: InterpretationProfile) {
+        self.rules = profile.rules();
+    }
+
+    // Own rules, like a profile with other coverage values
+    pub fn set_rules(&mut self, rules: InterpretationRules) {
+        self.rules = rules;
+    }
+
+    // Buildings stand on the terrain, not at height 0
+    pub fn set_elevation(&mut self, elevation: Arc<Elevation>) {
+        self.elevation = Some(elevation);
+    }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
     pub fn get_buildings_and_parts(self) -> BuildingsAndParts {
         self.buildings_or_parts
     }
 
+    pub fn get_buildings_and_parts_and_diagnostics(self) -> (BuildingsAndParts, Diagnostics) {
+        (self.buildings_or_parts, self.diagnostics)
+    }
+
+    // With the roads, water, landuse and trees around
+    pub fn get_buildings_and_parts_and_ground(self) -> (BuildingsAndParts, GroundFeatures) {
+        (self.buildings_or_parts, self.ground_features)
+    }
+
+    // The ids of the nodes and ways, referenced but not loaded
+    pub fn missing_elements(&self) -> &MissingElements {
+        &self.missing
+    }
+
+    fn parse_color(
+        &mut self,
+        color: Option<&String>,
+        default: RenderColor,
+        id: u64,
+    ) -> RenderColor {
+        let Some(color) = color else {
+            return default;
+        };
+        match self.palette.parse_color(color) {
+            Some(color) => color,
+            None => {
+                self.diagnostics.push(Diagnostic {
+                    id,
+                    kind: DiagnosticKind::UnknownColor,
+                    value: color.clone(),
+                });
+                DEFAULT_BAD_COLOR
+            }
+        }
+    }
+
     ///////////////////////
 
-    pub fn add_node(&mut self, id: u64, latitude: f64, longitude: f64, _tags: Option<OsmMap>) {
-        let position = if self.gpu_ground_null_coordinates.latitude == 0.0 {
-            GroundPosition {
+    pub fn add_node(&mut self, id: u64, latitude: f64, longitude: f64, tags: Option<OsmMap>) {
+        let position = self
+            .projection
+            .map(|projection| projection.forward(latitude, longitude))
+            .unwrap_or(GroundPosition {
                 y: latitude,
                 x: longitude,
-            }
-        } else {
-            self.gpu_ground_null_coordinates
-                .coordinates_to_position(latitude, longitude)
-        };
-        self.nodes_map.insert(id, OsmNode { position });
+            });
+        self.nodes_map.insert(id, OsmNode { position, tags });
     }
 
     pub fn add_way(&mut self, id: u64, mut nodes: Vec<u64>, tags: Option<OsmMap>) {
-        if nodes.first().unwrap() == nodes.last().unwrap() {
+        if nodes.is_empty() {
+            println!("Way without nodes! id: {}", id);
+            return;
+        }
+        if nodes.first() == nodes.last() {
             if nodes.len() < 3 {
                 println!("Closed way with < 3 corners! id: {}", id);
                 return;
@@ -277,9 +412,18 @@ impl Osm2Layer {
 
     pub fn add_line(&mut self, id: u64, nodes: Vec<u64>, tags: Option<OsmMap>) {
         let mut positions = Vec::new();
+        let mut gaps = Vec::new();
         for node_id in nodes {
-            let position = self.nodes_map.get(&node_id).unwrap().position;
-            positions.push(position);
+            // Nodes outside of the loaded area are missing
+            let Some(node) = self.nodes_map.get(&node_id) else {
+                println!("Line {id}: missing node {node_id}");
+                self.missing_nodes.entry(id).or_default().push(node_id);
+                if gaps.last() != Some(&positions.len()) {
+                    gaps.push(positions.len());
+                }
+                continue;
+            };
+            positions.push(node.position);
         }
 
         self.lines_map.insert(
@@ -288,27 +432,45 @@ impl Osm2Layer {
                 id,
                 positions,
                 tags,
+                gaps,
             },
         );
     }
 
     pub fn add_area(&mut self, id: u64, nodes: Vec<u64>, tags: Option<OsmMap>) {
         let mut footprint = Footprint::new();
+        let mut incomplete = false;
         for node_id in nodes {
-            let position = self.nodes_map.get(&node_id).unwrap().position;
-            footprint.push_position(position);
+            let Some(node) = self.nodes_map.get(&node_id) else {
+                println!("Area {id}: missing node {node_id}");
+                self.missing_nodes.entry(id).or_default().push(node_id);
+                incomplete = true;
+                continue;
+            };
+            footprint.push_position(node.position);
         }
         footprint.close();
+        let building = tags
+            .as_ref()
+            .is_some_and(|tags| tags_get_yes(tags, "building").is_some());
+        let part = tags
+            .as_ref()
+            .is_some_and(|tags| tags_get_yes(tags, "building:part").is_some());
+        if building || part {
+            self.note_missing_nodes(id);
+        }
+        if footprint.multipolygon.0.is_empty() {
+            println!("Area {id}: less than 3 corners");
+            return;
+        }
 
         // When needs a buidling also to be a part? This example is just a building:
         // https://www.openstreetmap.org/edit#map=22/51.4995203/-0.1290937
         // So building else if solves it??? Overpass vor beeng both and check
-        if let Some(tags) = &tags {
-            if tags_get_yes(tags, "building").is_some() {
-                self.buildings.push(id);
-            } else if tags_get_yes(tags, "building:part").is_some() {
-                self.parts.push(id);
-            }
+        if building {
+            self.buildings.push(id);
+        } else if part {
+            self.parts.push(id);
         }
 
         // Now, as the tags are checked, they may get moved into the map
@@ -318,10 +480,27 @@ impl Osm2Layer {
                 _id: id,
                 footprint,
                 tags,
+                incomplete,
             },
         );
     }
 
+    // The not loaded nodes of a building, a part or a member of them are worth loading
+    fn note_missing_nodes(&mut self, way_id: u64) {
+        if let Some(nodes) = self.missing_nodes.get(&way_id) {
+            self.missing.nodes.extend(nodes);
+        }
+    }
+
+    // A member, loaded with less than 3 corners, needs its nodes, not itself again
+    fn note_missing_way(&mut self, way_id: u64) {
+        if self.missing_nodes.contains_key(&way_id) {
+            self.note_missing_nodes(way_id);
+        } else {
+            self.missing.ways.insert(way_id);
+        }
+    }
+
     pub fn add_relation(&mut self, id: u64, members: Members, tags: Option<OsmMap>) {
         if tags.is_none() {
             println!("Relation without tags: {id}");
@@ -346,7 +525,12 @@ impl Osm2Layer {
     ///////////////////////
 
     // Souldn't we have MORE sub fn's ???
-    fn create_building_or_part(&mut self, id: u64, osm_way: &mut OsmArea) {
+    fn create_building_or_part(
+        &mut self,
+        id: u64,
+        osm_way: &mut OsmArea,
+        parent_tags: Option<&OsmMap>,
+    ) {
         //println!("scan: way id = {:?}", id);
         if self.show_only > 0 && id != self.show_only {
             return;
@@ -359,6 +543,13 @@ impl Osm2Layer {
             println!("create_building_or_part: way is empty {:?}", id);
             return;
         }
+        if osm_way.incomplete {
+            self.diagnostics.push(Diagnostic {
+                id,
+                kind: DiagnosticKind::Incomplete,
+                value: "nodes or members outside of the loaded data".into(),
+            });
+        }
 
         // // // // // // // // //
 
@@ -376,6 +567,10 @@ impl Osm2Layer {
                 <= COMPLEX_MIN_NODES;
         //&& osm_way.footprint.multipolygon[FIRST_POLYGON][OUTER_POLYGON].len() <= 6;
 
+        let rules = self.rules;
+        let parent_building_type = parent_tags.and_then(|tags| tags.get("building"));
+        let building_type = tags_get_yes(tags, "building").or(parent_building_type);
+
         // ** Shape of the roof. All buildings have a roof, even if it is not tagged **
         let roof_shape: RoofShape = match tags.get("roof:shape") {
             Some(roof_shape) => match roof_shape.as_str() {
@@ -387,35 +582,65 @@ impl Osm2Layer {
                 "onion" => RoofShape::Onion,
                 _ => {
                     // println!("Warning: roof_shape Unknown: {}", roof_shape);
-                    if simple_footprint {
+                    if rules.unknown_roof_gabled && simple_footprint {
                         RoofShape::Gabled // todo: geographic dependend ggg
                     } else {
                         RoofShape::Flat
                     }
                 }
             },
-            None => RoofShape::Flat,
+            None => {
+                let is_house = building_type.is_some_and(|building| {
+                    matches!(
+                        building.as_str(),
+                        "house" | "detached" | "semidetached_house" | "terrace" | "farm"
+                    )
+                });
+                if rules.house_roof_gabled && is_house && !part {
+                    RoofShape::Gabled
+                } else {
+                    RoofShape::Flat
+                }
+            }
         };
 
         // ** Colors and Materials **
-        let building_color = parse_color(
+        let building_color = self.parse_color(
             tags_get3(tags, "building:colour", "colour", "building:material"),
             DEFAULT_WALL_COLOR,
             id,
         );
         // Should parts for default get the red DEFAULT_ROOF_COLOR or DEFAULT_WALL_COLOR or the given wall color?
-        let roof_color = parse_color(
-            tags_get2(tags, "roof:colour", "roof:material"), // todo: parse_material
-            if part {
-                building_color
-            } else {
-                // if it is a simple building with only a view corners: Red roof (and gabled???)
-                if simple_footprint {
-                    DEFAULT_ROOF_RED
+        let default_roof_color = match rules.roof_color {
+            RoofColorDefault::Guess => {
+                if part {
+                    building_color
                 } else {
-                    DEFAULT_WALL_COLOR
+                    // if it is a simple building with only a view corners: Red roof (and gabled???)
+                    if simple_footprint {
+                        DEFAULT_ROOF_RED
+                    } else {
+                        DEFAULT_WALL_COLOR
+                    }
                 }
+            }
+            RoofColorDefault::Neutral => DEFAULT_WALL_COLOR,
+            RoofColorDefault::InheritFromBuilding => {
+                let building_roof_color =
+                    parent_tags.and_then(|tags| tags_get2(tags, "roof:colour", "roof:material"));
+                match building_roof_color {
+                    Some(color) => self.parse_color(Some(color), building_color, id),
+                    None => building_color,
+                }
+            }
+            RoofColorDefault::ByRoofShape => match roof_shape {
+                RoofShape::Flat | RoofShape::None => DEFAULT_WALL_COLOR,
+                _ => DEFAULT_ROOF_RED,
             },
+        };
+        let roof_color = self.parse_color(
+            tags_get2(tags, "roof:colour", "roof:material"), // todo: parse_material
+            default_roof_color,
             id,
         );
 
@@ -427,28 +652,31 @@ impl Osm2Layer {
             _ => 2.0, //DEFAULT_ROOF_HEIGHT,
         };
 
-        // ** Heights **  // todo: a new fn process_heights
-        let min_height = parse_height(tags.get("min_height")); // DEFAULT_MIN_HEIGHT
-        let mut roof_height = parse_height(tags.get("roof:height"));
-        let roof_levels = parse_height(tags.get("roof:levels"));
-        if roof_height == 0. && roof_levels > 0. {
-            roof_height = roof_levels * 3.0;
-        }
-        if roof_height == 0. {
-            roof_height = default_roof_heigt;
-        }
-        //println!( "roof_height: {roof_height} default_roof_heigt: {default_roof_heigt} roof_shape: {:?}", roof_shape);
-        //let wall_height = parse_height(tags.get("height"), 6.0 /*DEFAULT_WALL_HEIGHT*/) - roof_height;
+        // ** Heights **
+        // Parts have no building type. They get the level height of their building
+        let Heights {
+            min_height,
+            wall_height,
+            roof_height,
+            levels,
+            min_level,
+            roof_levels,
+            level_height,
+            underground_levels,
+            underground,
+        } = process_heights(
+            tags,
+            building_type,
+            roof_shape,
+            default_roof_heigt,
+            rules.default_wall_height,
+            id,
+            &mut self.diagnostics,
+        );
 
-        let mut building_height = parse_height(tags_get2(tags, "building:height", "height"));
-        let levels = parse_height(tags_get2(tags, "building:levels", "building:levels"));
-        if building_height == 0. && levels > 0. {
-            building_height = levels * 3.0 + roof_height;
-        }
-        if building_height == 0. {
-            building_height = DEFAULT_WALL_HEIGHT;
-        }
-        let wall_height = building_height - roof_height;
+        // ** Facade and special structures **
+        let facade = parse_facade(tags);
+        let structure = parse_structure(tags, min_height, wall_height);
 
         // ** Roof direction and Orientation **
 
@@ -469,6 +697,7 @@ impl Osm2Layer {
         }
 
         let roof_direction = /*parse_orientation???*/ tags.get("roof:direction");
+        let mut roof_snap = RoofSnap::NotTagged;
         if let Some(direction) = roof_direction {
             //println!("roof:direction {direction}");
             orienaton_by = Orientation::ByNauticDirction;
@@ -493,8 +722,12 @@ impl Osm2Layer {
                 "WNW" => roof_angle = f64::to_radians(292.),
                 "NNW" => roof_angle = f64::to_radians(337.),
                 _ => {
-                    let value = direction.parse();
-                    if let Ok(value) = value {
+                    // "NaN" and "inf" parse too, but are no direction
+                    let value = direction
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|value| value.is_finite());
+                    if let Some(value) = value {
                         roof_angle = circle_limit(f64::to_radians(value));
                         orienaton_by = Orientation::ByAngleValue;
                     } else {
@@ -502,6 +735,29 @@ impl Osm2Layer {
                     }
                 }
             }
+
+            // Snap to the closest edge. roof:orientation is then relative to that edge
+            let tolerance = match orienaton_by {
+                Orientation::ByAngleValue => ANGLE_SNAP_TOLERANCE,
+                _ => COMPASS_SNAP_TOLERANCE,
+            };
+            let tagged_angle = roof_angle;
+            roof_snap = match osm_way
+                .footprint
+                .snap_to_edge_normal(roof_angle, f64::to_radians(tolerance))
+            {
+                Some(normal) => {
+                    roof_angle = normal;
+                    RoofSnap::Snapped { tagged_angle }
+                }
+                None => RoofSnap::Unsnapped,
+            };
+            if roof_orientation.is_some_and(|orientation| orientation == "across")
+                && matches!(roof_snap, RoofSnap::Snapped { .. })
+                && !matches!(roof_shape, RoofShape::Skillion)
+            {
+                roof_angle = circle_limit(roof_angle + f64::to_radians(90.));
+            }
         }
 
         // Not here at the parameter, but in the fn rotate against the actual angle to got 0 degrees
@@ -543,10 +799,22 @@ impl Osm2Layer {
             bounding_box_rotated,
             wall_height,
             min_height,
+            ground_height: 0.,
+            coverage: PartCoverage::Inside,
+            incomplete: osm_way.incomplete,
+            levels,
+            min_level,
+            roof_levels,
+            level_height,
+            underground_levels,
+            underground,
+            facade,
+            structure,
             building_color,
             roof_shape,
             roof_height,
             roof_angle,
+            roof_snap,
             roof_color,
         };
 
@@ -565,6 +833,8 @@ impl Osm2Layer {
 
         #[cfg(debug_assertions)]
         println!("\n**** process {:?} ways", self.buildings.len());
+        // Parts, which overlap a building, but too little to belong to it
+        let mut not_belonging = HashMap::new();
         while let Some(building_id) = self.buildings.pop() {
             if self.show_only > 0 && self.show_only != building_id {
                 continue;
@@ -591,6 +861,8 @@ impl Osm2Layer {
             // Subtract parts from building outer ways - code is slow? Todo!
             // is parts cloned or part_id???
             let mut part_index: i32 = -1;
+            let mut has_parts = false;
+            let first_of_building = self.buildings_or_parts.len();
             for part_id in self.parts.clone() {
                 part_index += 1;
                 if part_id == 0 {
@@ -606,14 +878,19 @@ impl Osm2Layer {
 
                 if !outer_area
                     .bounding_box
-                    .contains(&part.footprint.bounding_box)
+                    .intersects(&part.footprint.bounding_box)
                 {
                     continue;
                 };
 
-                if !outer_area.other_is_inside(&part.footprint) {
+                let coverage =
+                    classify_part(&outer_area.multipolygon, &part.footprint.multipolygon);
+                if !coverage.belongs_to_building(&self.rules.coverage) {
                     //#[cfg(debug_assertions)]
-                    //println!("- part: {part_id}");
+                    //println!("- part: {part_id} {:?}", coverage);
+                    if let PartCoverage::Overlapping { .. } = coverage {
+                        not_belonging.insert(part_id, coverage);
+                    }
                     continue;
                 };
                 //println!("+ part: {part_id}");
@@ -621,8 +898,13 @@ impl Osm2Layer {
                 //#[cfg(debug_assertions)]
                 //println!("part: {part_id}");
                 building.footprint.subtract(&part.footprint);
+                has_parts = true;
                 let mut part = self.areas_map.remove(&part_id).unwrap();
-                self.create_building_or_part(part_id, &mut part);
+                let count_before = self.buildings_or_parts.len();
+                self.create_building_or_part(part_id, &mut part, building.tags.as_ref());
+                for created in &mut self.buildings_or_parts[count_before..] {
+                    created.coverage = coverage;
+                }
 
                 // Part 1144964446 is inner of (5465171 AND 15475567) ???
                 // This would be nice to drop used parts and spare time.
@@ -632,6 +914,11 @@ impl Osm2Layer {
                 // if outer empty: continue to render more parts
             }
 
+            // Without the slivers along the parts. A building without parts stays as mapped
+            if has_parts {
+                building.footprint.multipolygon =
+                    remove_slivers(&building.footprint.multipolygon, &self.rules.coverage);
+            }
             let remaining_area_size = building.footprint.get_area_size();
             let percent_left = (remaining_area_size / outer_area_size * 100.) as i32;
 
@@ -642,11 +929,46 @@ impl Osm2Layer {
             //println!("\n\nbuilding.footprint: {:?}", building.footprint.polygons);
 
             // ??? 40 40. 20 20.
-            if !building.footprint.multipolygon.is_empty() && percent_left >= 20 {
-                self.create_building_or_part(building_id, &mut building);
+            let remains_shown = match self.rules.building_remains {
+                BuildingRemains::Hidden => !has_parts,
+                BuildingRemains::MinPercent(min_percent) => percent_left >= min_percent,
+            };
+            if !building.footprint.multipolygon.is_empty() && remains_shown {
+                self.create_building_or_part(building_id, &mut building, None);
+            }
+
+            // The building and all its parts stand on the same ground, found by the full outline
+            if let (Some(elevation), Some(projection)) = (&self.elevation, &self.projection) {
+                let ground_height = elevation
+                    .ground_height(projection, &outer_area.multipolygon)
+                    .unwrap_or(0.);
+                for building_or_part in &mut self.buildings_or_parts[first_of_building..] {
+                    building_or_part.ground_height = ground_height;
+                }
             }
         }
 
+        // Parts of no building are kept, to show the tagging error
+        if self.way_only == 0 {
+            for part_id in self.parts.clone() {
+                let Some(mut part) = self.areas_map.remove(&part_id) else {
+                    continue;
+                };
+                let count_before = self.buildings_or_parts.len();
+                self.create_building_or_part(part_id, &mut part, None);
+                let coverage = not_belonging
+                    .get(&part_id)
+                    .copied()
+                    .unwrap_or(PartCoverage::Outside);
+                for created in &mut self.buildings_or_parts[count_before..] {
+                    created.coverage = coverage;
+                }
+            }
+        }
+
+        self.cut_building_passages();
+        self.collect_ground_features();
+
         #[cfg(debug_assertions)]
         for part_id in &self.parts {
             if *part_id > 0 {
@@ -655,6 +977,132 @@ impl Osm2Layer {
         }
     }
 
+    // The ways and nodes, not used for buildings, which are shown flat on the ground
+    fn collect_ground_features(&mut self) {
+        for (id, line) in &self.lines_map {
+            let Some(tags) = &line.tags else {
+                continue;
+            };
+            if let Some((color, width, level)) = classify_line(tags) {
+                for positions in line.pieces() {
+                    self.ground_features.push(GroundFeature {
+                        id: *id,
+                        shape: GroundShape::Line { positions, width },
+                        color,
+                        level,
+                    });
+                }
+            }
+        }
+
+        for (id, area) in &self.areas_map {
+            let Some(tags) = &area.tags else {
+                continue;
+            };
+            if let Some((color, level)) = classify_area(tags) {
+                self.ground_features.push(GroundFeature {
+                    id: *id,
+                    shape: GroundShape::Area(area.footprint.clone()),
+                    color,
+                    level,
+                });
+            } else if let Some((color, width, level)) = classify_line(tags) {
+                // A closed highway without area=yes, like a roundabout, is a line
+                let Some(polygon) = area.footprint.multipolygon.iter().next() else {
+                    continue;
+                };
+                self.ground_features.push(GroundFeature {
+                    id: *id,
+                    shape: GroundShape::Line {
+                        positions: polygon.exterior().0.clone(),
+                        width,
+                    },
+                    color,
+                    level,
+                });
+            }
+        }
+
+        for (id, node) in &self.nodes_map {
+            let Some(tags) = &node.tags else {
+                continue;
+            };
+            if let Some((height, crown_diameter)) = tree_size(tags) {
+                self.ground_features.push(GroundFeature {
+                    id: *id,
+                    shape: GroundShape::Tree {
+                        position: node.position,
+                        height,
+                        crown_diameter,
+                    },
+                    color: CROWN_COLOR,
+                    level: 0.,
+                });
+            }
+        }
+
+        // Drawn from the lowest level up, in the same order each time
+        self.ground_features
+            .sort_by(|a, b| a.level.total_cmp(&b.level).then(a.id.cmp(&b.id)));
+    }
+
+    // tunnel=building_passage: Cut a passage through the walls at ground level.
+    // The building above the passage remains as a part with a raised min_height
+    fn cut_building_passages(&mut self) {
+        for line in self.lines_map.values() {
+            let Some(tags) = &line.tags else {
+                continue;
+            };
+            if tags
+                .get("tunnel")
+                .is_none_or(|tunnel| tunnel != "building_passage")
+            {
+                continue;
+            }
+            let passage = passage_polygon(line);
+            let Some(passage_box) = passage.bounding_rect() else {
+                continue;
+            };
+            let passage_height = match tags.get("maxheight") {
+                Some(maxheight) => parse_height(Some(maxheight)),
+                None => DEFAULT_PASSAGE_HEIGHT,
+            };
+
+            let mut above_passages = Vec::new();
+            for building_or_part in self.buildings_or_parts.iter_mut() {
+                if building_or_part.min_height >= passage_height
+                    || !building_or_part
+                        .footprint
+                        .bounding_box
+                        .intersects(&passage_box)
+                {
+                    continue;
+                }
+                let above = building_or_part
+                    .footprint
+                    .multipolygon
+                    .intersection(&passage);
+                if above.unsigned_area() < 0.1 {
+                    continue;
+                }
+                #[cfg(debug_assertions)]
+                println!("passage {} through {}", line.id, building_or_part.id);
+
+                building_or_part.footprint.multipolygon =
+                    building_or_part.footprint.multipolygon.difference(&passage);
+                if building_or_part.wall_height > passage_height {
+                    let mut above_passage = building_or_part.clone();
+                    above_passage.footprint.multipolygon = above;
+                    above_passage.min_height = passage_height;
+                    above_passages.push(above_passage);
+                }
+            }
+            self.buildings_or_parts.append(&mut above_passages);
+        }
+        self.buildings_or_parts
+            .retain(|building_or_part| !building_or_part.footprint.multipolygon.is_empty());
+    }
+
     ///////////////////////
 
     fn process_relation(&mut self, id: u64, osm_relation: &mut OsmRelation) {
@@ -700,6 +1148,7 @@ impl Osm2Layer {
         let mut relation_footprint = Footprint::new();
 
         self.outer_state = OuterState::New;
+        self.relation_incomplete = false;
 
         // first scann for outer, later vo inner
         for member in &members {
@@ -737,6 +1186,7 @@ impl Osm2Layer {
             _id: id, // self.first_outer_id,
             footprint: relation_footprint,
             tags: Some(tags.clone()),
+            incomplete: self.relation_incomplete,
         };
 
         self.areas_map.insert(
@@ -769,6 +1219,8 @@ impl Osm2Layer {
             }
             //println!("ttt outer: {:?}", area);
             new_footprint.set_from_other(&area.footprint);
+            self.relation_incomplete |= area.incomplete;
+            self.note_missing_nodes(outer_ref);
             self.outer_state = OuterState::Ready;
             self.first_outer_id_ = outer_ref;
             return;
@@ -785,25 +1237,37 @@ impl Osm2Layer {
             for position in &line.positions {
                 new_footprint.push_position(*position);
             }
+            self.relation_incomplete |= !line.gaps.is_empty();
+            self.note_missing_nodes(outer_ref);
             return;
         }
         println!("outer none, id/ref: {}", outer_ref);
+        self.note_missing_way(outer_ref);
+        self.relation_incomplete = true;
     }
 
-    fn process_relation_inner(&self, elements_ref: u64, new_footprint: &mut Footprint, id: u64) {
+    fn process_relation_inner(
+        &mut self,
+        elements_ref: u64,
+        new_footprint: &mut Footprint,
+        id: u64,
+    ) {
         //println!("elements_ref: {:?}", &elements_ref);
         //
-        let option = self.areas_map.get(&elements_ref);
-        if option.is_none() {
+        let Some(inner) = self.areas_map.get(&elements_ref) else {
             // May be outer is inside the load bbox, but inner not :-/
             println!("Relaton {id}: Inner way {} not loaded!", elements_ref);
+            self.note_missing_way(elements_ref);
+            self.relation_incomplete = true;
             return;
-        }
+        };
         #[cfg(debug_assertions)]
         println!("inner: {}", elements_ref);
 
         // todo: what if the hole is has holes? What if the polygon is a multipolygon?
-        new_footprint.subtract(&option.unwrap().footprint);
+        self.relation_incomplete |= inner.incomplete;
+        new_footprint.subtract(&inner.footprint);
+        self.note_missing_nodes(elements_ref);
         //println!("inner way; {:?}", &elements_ref);
     }
 }
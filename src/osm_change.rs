@@ -0,0 +1,507 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// osmChange: the created, modified and deleted elements of a changeset or an editor //////////////
+
+/*
+ * https://wiki.openstreetmap.org/wiki/OsmChange
+ * Read from .osc files, saved by editors like JOSM, or from /changeset/{id}/download.
+ * The elements become OSM JSON values, like the API sends them, to apply them on loaded data.
+ * The XML is read by hand, the crate has no XML parser. Only the tags of osmChange are known.
+ * New elements have negative ids. They are mapped to the top of u64: -1 to u64::MAX
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use bytes::Bytes;
+use serde_json::{Map, Value, json};
+
+use crate::kernel_in::{BuildingsAndParts, RenderColor};
+
+static CREATED_COLOR: RenderColor = [0.2, 0.75, 0.2, 1.0];
+static MODIFIED_COLOR: RenderColor = [0.95, 0.65, 0.1, 1.0];
+static DELETED_COLOR: RenderColor = [0.85, 0.15, 0.15, 1.0];
+static UNCHANGED_COLOR: RenderColor = [0.8, 0.8, 0.8, 1.0]; // so the changed stand out
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeAction {
+    Create,
+    Modify,
+    Delete,
+}
+
+impl std::fmt::Display for ChangeAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChangeAction::Create => write!(f, "created"),
+            ChangeAction::Modify => write!(f, "modified"),
+            ChangeAction::Delete => write!(f, "deleted"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OsmChange {
+    // In the order of the file, as OSM JSON elements
+    pub elements: Vec<(ChangeAction, Value)>,
+}
+
+impl OsmChange {
+    pub fn from_xml(xml: &str) -> Result<Self, String> {
+        let mut osm_change = Self::default();
+        let mut action = None;
+        let mut element: Option<Value> = None;
+        for tag in xml_tags(xml)? {
+            match tag {
+                XmlTag::Start {
+                    name,
+                    attributes,
+                    empty,
+                } => match name {
+                    "create" => action = Some(ChangeAction::Create),
+                    "modify" => action = Some(ChangeAction::Modify),
+                    "delete" => action = Some(ChangeAction::Delete),
+                    "node" | "way" | "relation" => {
+                        let Some(action) = action else {
+                            return Err(format!("{name} outside of create, modify or delete"));
+                        };
+                        let new_element = osm_element(name, &attributes)?;
+                        if empty {
+                            osm_change.elements.push((action, new_element));
+                        } else {
+                            element = Some(new_element);
+                        }
+                    }
+                    "tag" | "nd" | "member" => {
+                        let Some(element) = element.as_mut() else {
+                            return Err(format!("{name} outside of an element"));
+                        };
+                        add_to_element(element, name, &attributes)?;
+                    }
+                    _ => (), // osmChange, bounds etc.
+                },
+                XmlTag::End(name) => match name {
+                    "create" | "modify" | "delete" => action = None,
+                    "node" | "way" | "relation" => {
+                        if let (Some(action), Some(element)) = (action, element.take()) {
+                            osm_change.elements.push((action, element));
+                        }
+                    }
+                    _ => (),
+                },
+            }
+        }
+        Ok(osm_change)
+    }
+
+    // The action of each changed element, by type and id
+    pub fn actions(&self) -> HashMap<(String, u64), ChangeAction> {
+        self.elements
+            .iter()
+            .filter_map(|(action, element)| Some((element_key(element)?, *action)))
+            .collect()
+    }
+
+    /*
+     * The loaded OSM JSON with the change applied: created and modified elements replace
+     * or add to the loaded ones, deleted are removed. None if the data is no OSM JSON.
+     * The loaded data is taken as before the change, like for an .osc file from an editor.
+     * The API data of an uploaded changeset is already after it, see revert
+     */
+    pub fn apply(&self, osm_json: &[u8]) -> Option<Bytes> {
+        let mut json: Value = serde_json::from_slice(osm_json).ok()?;
+        let elements = json["elements"].as_array_mut()?;
+        let mut positions: HashMap<(String, u64), usize> = elements
+            .iter()
+            .enumerate()
+            .filter_map(|(position, element)| Some((element_key(element)?, position)))
+            .collect();
+        let mut deleted = vec![false; elements.len()];
+        for (action, element) in &self.elements {
+            let Some(key) = element_key(element) else {
+                continue;
+            };
+            match (action, positions.get(&key)) {
+                (ChangeAction::Delete, Some(position)) => deleted[*position] = true,
+                (ChangeAction::Delete, None) => (),
+                (_, Some(position)) => {
+                    elements[*position] = element.clone();
+                    deleted[*position] = false;
+                }
+                (_, None) => {
+                    positions.insert(key, elements.len());
+                    elements.push(element.clone());
+                    deleted.push(false);
+                }
+            }
+        }
+        let mut deleted = deleted.into_iter();
+        elements.retain(|_| !deleted.next().unwrap_or(false));
+        // The ways need their nodes before them, the order of each type is kept
+        elements.sort_by_key(|element| match element["type"].as_str() {
+            Some("node") => 0,
+            Some("way") => 1,
+            _ => 2,
+        });
+        Some(Bytes::from(serde_json::to_vec(&json).ok()?))
+    }
+
+    /*
+     * Type, id and version before the change of the modified and deleted elements.
+     * In a changeset download, the version is the one after it. Created ones have none
+     */
+    pub fn previous_versions(&self) -> Vec<(String, u64, u64)> {
+        self.elements
+            .iter()
+            .filter(|(action, _)| *action != ChangeAction::Create)
+            .filter_map(|(_, element)| {
+                let (element_type, id) = element_key(element)?;
+                let version = element["version"].as_u64().filter(|version| *version > 1)?;
+                Some((element_type, id, version - 1))
+            })
+            .collect()
+    }
+
+    /*
+     * The loaded OSM JSON as before an uploaded changeset, the API data is after it:
+     * created elements are removed, modified and deleted ones replaced by or added as their
+     * previous versions. Without a previous version, a modified element is kept as it is.
+     * None if the data is no OSM JSON
+     */
+    pub fn revert(&self, osm_json: &[u8], previous: &[Value]) -> Option<Bytes> {
+        let mut json: Value = serde_json::from_slice(osm_json).ok()?;
+        let elements = json["elements"].as_array_mut()?;
+        let actions = self.actions();
+        let mut previous: BTreeMap<(String, u64), &Value> = previous
+            .iter()
+            .filter(|element| element["visible"].as_bool() != Some(false))
+            .filter_map(|element| Some((element_key(element)?, element)))
+            .collect();
+        elements.retain_mut(|element| {
+            let Some(key) = element_key(element) else {
+                return true;
+            };
+            if actions.get(&key) == Some(&ChangeAction::Create) {
+                return false;
+            }
+            if let Some(previous) = previous.remove(&key) {
+                *element = previous.clone();
+            }
+            true
+        });
+        // Deleted in the changeset, so not loaded
+        elements.extend(previous.into_values().cloned());
+        elements.sort_by_key(|element| match element["type"].as_str() {
+            Some("node") => 0,
+            Some("way") => 1,
+            _ => 2,
+        });
+        Some(Bytes::from(serde_json::to_vec(&json).ok()?))
+    }
+
+    /*
+     * The actions of the ways and relations in the OSM JSON, by their id like BuildingOrPart.
+     * A way with moved, created or deleted nodes is modified, a relation with such members too
+     */
+    pub fn element_actions(&self, osm_json: &[u8]) -> HashMap<u64, ChangeAction> {
+        let actions = self.actions();
+        let mut element_actions = HashMap::new();
+        let Ok(json) = serde_json::from_slice::<Value>(osm_json) else {
+            return element_actions;
+        };
+        let elements = json["elements"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        let mut changed_ways = HashSet::new();
+        for element_type in ["way", "relation"] {
+            for element in elements {
+                if element["type"].as_str() != Some(element_type) {
+                    continue;
+                }
+                let Some(id) = element["id"].as_u64() else {
+                    continue;
+                };
+                let changed_node = element["nodes"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_u64)
+                    .any(|node| actions.contains_key(&("node".into(), node)));
+                // Deleted ways are not in the data after the change, but in the actions
+                let changed_member = element["members"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|member| Some((member["type"].as_str()?, member["ref"].as_u64()?)))
+                    .any(|(member_type, id)| {
+                        actions.contains_key(&(member_type.into(), id))
+                            || (member_type == "way" && changed_ways.contains(&id))
+                    });
+                let action = match actions.get(&(element_type.into(), id)) {
+                    Some(action) => *action,
+                    None if changed_node || changed_member => ChangeAction::Modify,
+                    None => continue,
+                };
+                if element_type == "way" {
+                    changed_ways.insert(id);
+                }
+                element_actions.insert(id, action);
+            }
+        }
+        element_actions
+    }
+}
+
+impl std::fmt::Display for OsmChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = |counted| {
+            self.elements
+                .iter()
+                .filter(|(action, _)| *action == counted)
+                .count()
+        };
+        write!(
+            f,
+            "{} created, {} modified, {} deleted",
+            count(ChangeAction::Create),
+            count(ChangeAction::Modify),
+            count(ChangeAction::Delete)
+        )
+    }
+}
+
+// Buildings and parts coloured by their action, the unchanged grey
+pub fn color_changes(
+    buildings_and_parts: &mut BuildingsAndParts,
+    element_actions: &HashMap<u64, ChangeAction>,
+) {
+    for building_or_part in buildings_and_parts {
+        let color = match element_actions.get(&building_or_part.id) {
+            Some(ChangeAction::Create) => CREATED_COLOR,
+            Some(ChangeAction::Modify) => MODIFIED_COLOR,
+            Some(ChangeAction::Delete) => DELETED_COLOR,
+            None => UNCHANGED_COLOR,
+        };
+        building_or_part.building_color = color;
+        building_or_part.roof_color = color;
+    }
+}
+
+fn element_key(element: &Value) -> Option<(String, u64)> {
+    Some((
+        element["type"].as_str()?.to_string(),
+        element["id"].as_u64()?,
+    ))
+}
+
+// A node, way or relation with its attributes, without tags, nodes and members yet
+fn osm_element(name: &str, attributes: &[(&str, String)]) -> Result<Value, String> {
+    let mut element = Map::new();
+    element.insert("type".into(), name.into());
+    for (key, value) in attributes {
+        let value = match *key {
+            "id" | "uid" | "version" | "changeset" => Value::from(osm_id(value)?),
+            "lat" | "lon" => Value::from(
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("{name} {key} is no number: {value}"))?,
+            ),
+            "visible" => Value::from(value == "true"),
+            _ => Value::from(value.as_str()),
+        };
+        element.insert(key.to_string(), value);
+    }
+    if !element.contains_key("id") {
+        return Err(format!("{name} without id"));
+    }
+    Ok(Value::Object(element))
+}
+
+fn add_to_element(
+    element: &mut Value,
+    name: &str,
+    attributes: &[(&str, String)],
+) -> Result<(), String> {
+    let attribute = |key: &str| {
+        attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == key)
+            .map(|(_, value)| value.as_str())
+            .ok_or(format!("{name} without {key}"))
+    };
+    match name {
+        "tag" => {
+            if !element["tags"].is_object() {
+                element["tags"] = json!({});
+            }
+            element["tags"][attribute("k")?] = attribute("v")?.into();
+        }
+        "nd" => {
+            if !element["nodes"].is_array() {
+                element["nodes"] = json!([]);
+            }
+            if let Some(nodes) = element["nodes"].as_array_mut() {
+                nodes.push(osm_id(attribute("ref")?)?.into());
+            }
+        }
+        _ => {
+            if !element["members"].is_array() {
+                element["members"] = json!([]);
+            }
+            let member = json!({
+                "type": attribute("type")?,
+                "ref": osm_id(attribute("ref")?)?,
+                "role": attribute("role").unwrap_or(""),
+            });
+            if let Some(members) = element["members"].as_array_mut() {
+                members.push(member);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn osm_id(text: &str) -> Result<u64, String> {
+    match text.parse::<i64>() {
+        Ok(id) if id >= 0 => Ok(id as u64),
+        Ok(id) => Ok(u64::MAX - (id.unsigned_abs() - 1)),
+        Err(_) => Err(format!("{text} is no id")),
+    }
+}
+
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// XML, only as much as osmChange needs: tags with attributes, no text content
+
+enum XmlTag<'a> {
+    Start {
+        name: &'a str,
+        attributes: Vec<(&'a str, String)>,
+        empty: bool, // like <nd ref="1"/>
+    },
+    End(&'a str),
+}
+
+fn xml_tags(xml: &str) -> Result<Vec<XmlTag<'_>>, String> {
+    let mut tags = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start + 1..];
+        // Comments, <?xml ...?> and <!DOCTYPE ...>
+        if let Some(comment) = rest.strip_prefix("!--") {
+            let end = comment.find("-->").ok_or("Comment without end")?;
+            rest = &comment[end + 3..];
+            continue;
+        }
+        if rest.starts_with('?') || rest.starts_with('!') {
+            let end = rest.find('>').ok_or("Declaration without end")?;
+            rest = &rest[end + 1..];
+            continue;
+        }
+        if let Some(end_tag) = rest.strip_prefix('/') {
+            let end = end_tag.find('>').ok_or("End tag without >")?;
+            tags.push(XmlTag::End(end_tag[..end].trim()));
+            rest = &end_tag[end + 1..];
+            continue;
+        }
+
+        let name_end = rest
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .ok_or("Tag without end")?;
+        let name = &rest[..name_end];
+        rest = &rest[name_end..];
+        let mut attributes = Vec::new();
+        loop {
+            rest = rest.trim_start();
+            if let Some(after) = rest.strip_prefix("/>") {
+                tags.push(XmlTag::Start {
+                    name,
+                    attributes,
+                    empty: true,
+                });
+                rest = after;
+                break;
+            }
+            if let Some(after) = rest.strip_prefix('>') {
+                tags.push(XmlTag::Start {
+                    name,
+                    attributes,
+                    empty: false,
+                });
+                rest = after;
+                break;
+            }
+            let equal = rest
+                .find('=')
+                .ok_or(format!("Attribute of {name} without value"))?;
+            let key = rest[..equal].trim();
+            let value = rest[equal + 1..].trim_start();
+            let quote = value
+                .chars()
+                .next()
+                .filter(|quote| *quote == '"' || *quote == '\'')
+                .ok_or(format!("Attribute {key} of {name} without quotes"))?;
+            let value = &value[1..];
+            let end = value
+                .find(quote)
+                .ok_or(format!("Attribute {key} of {name} without end"))?;
+            attributes.push((key, unescape(&value[..end])));
+            rest = &value[end + 1..];
+        }
+    }
+    Ok(tags)
+}
+
+// For attribute values, in quotes
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+// &amp; &lt; &gt; &quot; &apos; and &#number; or &#xhex;
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(';') else {
+            break;
+        };
+        let entity = &rest[1..end];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse))
+                .and_then(Result::ok)
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(character) => {
+                unescaped.push(character);
+                rest = &rest[end + 1..];
+            }
+            // Not an entity, keep the &
+            None => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
@@ -0,0 +1,119 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Interpretation profiles: the same tags, read like different 3D renderers do ////////////////////
+
+use std::str::FromStr;
+
+use crate::coverage::CoverageRules;
+use crate::osm2layers::DEFAULT_WALL_HEIGHT;
+
+// Mappers tune buildings to look right in one renderer. A profile shows them like that renderer
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InterpretationProfile {
+    #[default]
+    Obi, // the own guesses of this crate
+    S3dbStrict, // only what the Simple 3D Buildings wiki page defines
+    F4map,
+    Osm2World,
+}
+
+pub static ALL_PROFILES: [InterpretationProfile; 4] = [
+    InterpretationProfile::Obi,
+    InterpretationProfile::S3dbStrict,
+    InterpretationProfile::F4map,
+    InterpretationProfile::Osm2World,
+];
+
+// The roof colour, if roof:colour and roof:material are not tagged
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoofColorDefault {
+    Guess,               // parts: the wall colour, small buildings: red, others: grey
+    Neutral,             // grey
+    InheritFromBuilding, // parts: roof:colour of the building, then the wall colour
+    ByRoofShape,         // pitched roofs red, flat roofs grey
+}
+
+// What to do with the area of a building, not covered by its parts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BuildingRemains {
+    Hidden,          // A building with parts is not shown
+    MinPercent(i32), // shown, if at least this percent of the building area is left
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InterpretationRules {
+    pub default_wall_height: f64,
+    pub roof_color: RoofColorDefault,
+    pub building_remains: BuildingRemains,
+    pub coverage: CoverageRules,
+    pub unknown_roof_gabled: bool, // an unknown roof:shape on a small footprint is gabled
+    pub house_roof_gabled: bool,   // no roof:shape on a house is gabled
+}
+
+impl InterpretationProfile {
+    // The values are approximations of the renderers, found by comparing, not by their code
+    pub fn rules(&self) -> InterpretationRules {
+        match self {
+            InterpretationProfile::Obi => InterpretationRules {
+                default_wall_height: DEFAULT_WALL_HEIGHT,
+                roof_color: RoofColorDefault::Guess,
+                building_remains: BuildingRemains::MinPercent(20),
+                coverage: CoverageRules::default(),
+                unknown_roof_gabled: true,
+                house_roof_gabled: false,
+            },
+            InterpretationProfile::S3dbStrict => InterpretationRules {
+                default_wall_height: DEFAULT_WALL_HEIGHT,
+                roof_color: RoofColorDefault::Neutral,
+                building_remains: BuildingRemains::Hidden,
+                coverage: CoverageRules::default(),
+                unknown_roof_gabled: false,
+                house_roof_gabled: false,
+            },
+            InterpretationProfile::F4map => InterpretationRules {
+                default_wall_height: 3.0,
+                roof_color: RoofColorDefault::InheritFromBuilding,
+                building_remains: BuildingRemains::MinPercent(1),
+                coverage: CoverageRules::default(),
+                unknown_roof_gabled: false,
+                house_roof_gabled: false,
+            },
+            InterpretationProfile::Osm2World => InterpretationRules {
+                default_wall_height: 7.5, // 3 levels of 2.5 meters
+                roof_color: RoofColorDefault::ByRoofShape,
+                building_remains: BuildingRemains::Hidden,
+                coverage: CoverageRules::default(),
+                unknown_roof_gabled: true,
+                house_roof_gabled: true,
+            },
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            InterpretationProfile::Obi => "obi",
+            InterpretationProfile::S3dbStrict => "s3db",
+            InterpretationProfile::F4map => "f4map",
+            InterpretationProfile::Osm2World => "osm2world",
+        }
+    }
+}
+
+impl FromStr for InterpretationProfile {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        ALL_PROFILES
+            .iter()
+            .find(|profile| profile.name() == name.to_lowercase())
+            .copied()
+            .ok_or(format!(
+                "Unknown profile {name}, use one of: obi, s3db, f4map, osm2world"
+            ))
+    }
+}
+
+impl std::fmt::Display for InterpretationProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
@@ -0,0 +1,119 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Local tangent plane projection (East-North-Up) on the WGS84 ellipsoid ///////////////////////////
+
+use crate::kernel_in::{GeographicCoordinates, GroundPosition};
+
+// https://en.wikipedia.org/wiki/World_Geodetic_System#WGS84
+pub static WGS84_A: f64 = 6_378_137.0; // semi-major axis in meters
+pub static WGS84_F: f64 = 1. / 298.257_223_563; // flattening
+static INVERSE_ITERATIONS: usize = 5; // the error is below a millimeter after 3
+
+/*
+ * The plane touches the ellipsoid at the origin. x points east, y north, in meters.
+ * Other than the equirectangular approximation, distances and angles stay right
+ * for large areas and at any latitude, including the equator.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct LocalProjection {
+    origin: GeographicCoordinates,
+    origin_ecef: [f64; 3],
+    sin_lat: f64,
+    cos_lat: f64,
+    sin_lon: f64,
+    cos_lon: f64,
+}
+
+impl LocalProjection {
+    pub fn new(origin: GeographicCoordinates) -> Self {
+        let (sin_lat, cos_lat) = origin.latitude.to_radians().sin_cos();
+        let (sin_lon, cos_lon) = origin.longitude.to_radians().sin_cos();
+        Self {
+            origin,
+            origin_ecef: geodetic_to_ecef(origin.latitude, origin.longitude, 0.),
+            sin_lat,
+            cos_lat,
+            sin_lon,
+            cos_lon,
+        }
+    }
+
+    pub fn origin(&self) -> GeographicCoordinates {
+        self.origin
+    }
+
+    // lat/lon in degrees to meters east and north of the origin. The height above the plane is dropped
+    pub fn forward(&self, latitude: f64, longitude: f64) -> GroundPosition {
+        let [east, north, _up] = self.ecef_to_enu(geodetic_to_ecef(latitude, longitude, 0.));
+        GroundPosition { x: east, y: north }
+    }
+
+    /*
+     * Meters east and north of the origin back to lat/lon in degrees.
+     * The point on the ellipsoid is below the plane. Its depth is unknown,
+     * so it is corrected until forward gives the same position.
+     */
+    pub fn inverse(&self, position: GroundPosition) -> GeographicCoordinates {
+        let mut target = position;
+        let mut coordinates = self.origin;
+        for _ in 0..INVERSE_ITERATIONS {
+            let ecef = self.enu_to_ecef([target.x, target.y, 0.]);
+            coordinates = ecef_to_geodetic(ecef);
+            let reached = self.forward(coordinates.latitude, coordinates.longitude);
+            target.x += position.x - reached.x;
+            target.y += position.y - reached.y;
+        }
+        coordinates
+    }
+
+    fn ecef_to_enu(&self, ecef: [f64; 3]) -> [f64; 3] {
+        let dx = ecef[0] - self.origin_ecef[0];
+        let dy = ecef[1] - self.origin_ecef[1];
+        let dz = ecef[2] - self.origin_ecef[2];
+        [
+            -self.sin_lon * dx + self.cos_lon * dy,
+            -self.sin_lat * self.cos_lon * dx - self.sin_lat * self.sin_lon * dy
+                + self.cos_lat * dz,
+            self.cos_lat * self.cos_lon * dx + self.cos_lat * self.sin_lon * dy + self.sin_lat * dz,
+        ]
+    }
+
+    fn enu_to_ecef(&self, enu: [f64; 3]) -> [f64; 3] {
+        let [east, north, up] = enu;
+        [
+            self.origin_ecef[0] - self.sin_lon * east - self.sin_lat * self.cos_lon * north
+                + self.cos_lat * self.cos_lon * up,
+            self.origin_ecef[1] + self.cos_lon * east - self.sin_lat * self.sin_lon * north
+                + self.cos_lat * self.sin_lon * up,
+            self.origin_ecef[2] + self.cos_lat * north + self.sin_lat * up,
+        ]
+    }
+}
+
+// Earth-centered, earth-fixed cartesian coordinates in meters
+fn geodetic_to_ecef(latitude: f64, longitude: f64, height: f64) -> [f64; 3] {
+    let e2 = WGS84_F * (2. - WGS84_F);
+    let (sin_lat, cos_lat) = latitude.to_radians().sin_cos();
+    let (sin_lon, cos_lon) = longitude.to_radians().sin_cos();
+    let n = WGS84_A / (1. - e2 * sin_lat * sin_lat).sqrt(); // prime vertical radius
+    [
+        (n + height) * cos_lat * cos_lon,
+        (n + height) * cos_lat * sin_lon,
+        (n * (1. - e2) + height) * sin_lat,
+    ]
+}
+
+// Bowring's method. One step is exact to a millimeter on the ground
+fn ecef_to_geodetic(ecef: [f64; 3]) -> GeographicCoordinates {
+    let [x, y, z] = ecef;
+    let e2 = WGS84_F * (2. - WGS84_F);
+    let b = WGS84_A * (1. - WGS84_F);
+    let ep2 = e2 / (1. - e2);
+    let p = x.hypot(y);
+    let theta = (z * WGS84_A).atan2(p * b);
+    let (sin_theta, cos_theta) = theta.sin_cos();
+    let latitude = (z + ep2 * b * sin_theta.powi(3)).atan2(p - e2 * WGS84_A * cos_theta.powi(3));
+    GeographicCoordinates {
+        latitude: latitude.to_degrees(),
+        longitude: y.atan2(x).to_degrees(),
+    }
+}
@@ -0,0 +1,159 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// Cache of the OSM API responses on disk ///////////////////////////////////////////////////////
+
+/*
+ * One file per URL, named by a hash of it, with a .meta file holding the URL and ETag.
+ * Offline, the cached responses are replayed, like stored fixtures.
+ * The input module decides if a response is still valid: by the ETag, if the API sends one,
+ * by the versions of the elements in it, or by the changesets in its area since it was loaded.
+ * See InputOsm::with_cache
+ */
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    directory: PathBuf,
+    refresh: bool, // load again and overwrite the cached responses
+    offline: bool, // only the cached responses, no requests
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub bytes: Bytes,
+    pub etag: Option<String>,
+    pub written: Option<u64>, // seconds since 1970
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CacheMeta {
+    url: String,
+    etag: Option<String>,
+    #[serde(default)]
+    written: Option<u64>,
+}
+
+impl ResponseCache {
+    pub fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.into(),
+            refresh: false,
+            offline: false,
+        }
+    }
+
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    pub fn refresh(&self) -> bool {
+        self.refresh
+    }
+
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    // None if not cached or a refresh is requested
+    pub fn read(&self, url: &str) -> Option<CachedResponse> {
+        if self.refresh {
+            return None;
+        }
+        let meta = std::fs::read(self.path(url, "meta")).ok()?;
+        let meta: CacheMeta = serde_json::from_slice(&meta).ok()?;
+        // Two URLs with the same hash
+        if meta.url != url {
+            return None;
+        }
+        let bytes = std::fs::read(self.path(url, "json")).ok()?;
+        Some(CachedResponse {
+            bytes: Bytes::from(bytes),
+            etag: meta.etag,
+            written: meta.written,
+        })
+    }
+
+    pub fn write(&self, url: &str, bytes: &[u8], etag: Option<&str>) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        let meta = CacheMeta {
+            url: url.into(),
+            etag: etag.map(|etag| etag.into()),
+            written: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|since| since.as_secs()),
+        };
+        std::fs::write(self.path(url, "json"), bytes)?;
+        std::fs::write(self.path(url, "meta"), serde_json::to_vec(&meta)?)
+    }
+
+    fn path(&self, url: &str, extension: &str) -> PathBuf {
+        self.directory
+            .join(format!("{:016x}.{extension}", url_hash(url)))
+    }
+}
+
+// FNV-1a, stable over Rust versions, unlike the DefaultHasher
+fn url_hash(url: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in url.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+// The version of each element in OSM JSON, by type and id
+pub fn element_versions(bytes: &[u8]) -> HashMap<(String, u64), u64> {
+    let mut versions = HashMap::new();
+    let Ok(json) = serde_json::from_slice::<serde_json::Value>(bytes) else {
+        return versions;
+    };
+    for element in json["elements"].as_array().into_iter().flatten() {
+        if let (Some(element_type), Some(id), Some(version)) = (
+            element["type"].as_str(),
+            element["id"].as_u64(),
+            element["version"].as_u64(),
+        ) {
+            versions.insert((element_type.into(), id), version);
+        }
+    }
+    versions
+}
+
+// Seconds since 1970 like 2023-05-22T12:26:48Z, as the API writes times
+pub fn iso_time(seconds: u64) -> String {
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let time = seconds % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
@@ -0,0 +1,320 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// CPU renderer: the meshes as PNG image, without a GPU. For CI and review thumbnails ////////////
+
+/*
+ * Z-buffered and flat shaded: one colour per triangle, lit by the sun of the Bevy UI.
+ * Back faces are culled like the Bevy material does. Triangles reaching behind the camera
+ * are clipped at the near plane.
+ */
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use crate::kernel_out::{OsmMeshAttributes, RenderColor, RenderPosition};
+
+static FIELD_OF_VIEW: f32 = std::f32::consts::PI / 4.; // vertical, like the Bevy default camera
+static NEAR: f32 = 0.1; // meters
+static AMBIENT: f32 = 0.4;
+static SKY_COLOR: [u8; 3] = [128, 128, 255]; // like ClearColor of the Bevy UI
+static GROUND_COLOR: [u8; 3] = [150, 255, 150];
+// To the sun, the opposite of the DirectionalLight direction of the Bevy UI
+static TO_LIGHT: [f32; 3] = [0.5, std::f32::consts::FRAC_1_SQRT_2, 0.5];
+
+#[derive(Clone, Copy, Debug)]
+pub struct SnapshotCamera {
+    pub azimuth: f32,   // degrees, the camera looks to: 0 = north, 90 = east
+    pub elevation: f32, // degrees, the camera looks down from above the horizon
+    pub distance: f32,  // meters to the center of the meshes
+    pub width: u32,     // pixels
+    pub height: u32,
+}
+
+impl Default for SnapshotCamera {
+    fn default() -> Self {
+        Self {
+            azimuth: 20.,
+            elevation: 30.,
+            distance: 100.,
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
+// RGBA pixels, rows from top to bottom
+pub struct Snapshot {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn write_png(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let writer = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        Ok(())
+    }
+}
+
+// The view of the camera: its position and axes
+struct View {
+    eye: [f32; 3],
+    right: [f32; 3],
+    up: [f32; 3],
+    forward: [f32; 3],
+    scale: f32, // view to pixels
+    width: f32,
+    height: f32,
+}
+
+impl View {
+    fn new(camera: &SnapshotCamera, target: [f32; 3]) -> Self {
+        let (sin_azimuth, cos_azimuth) = camera.azimuth.to_radians().sin_cos();
+        // Straight down, there is no right and up
+        let elevation = camera.elevation.clamp(-89.9, 89.9);
+        let (sin_elevation, cos_elevation) = elevation.to_radians().sin_cos();
+        // GPU axes: x east, y up, -z north
+        let forward = [
+            sin_azimuth * cos_elevation,
+            -sin_elevation,
+            -cos_azimuth * cos_elevation,
+        ];
+        let right = normalize(cross(forward, [0., 1., 0.]));
+        let up = cross(right, forward);
+        let eye = [
+            target[0] - forward[0] * camera.distance,
+            target[1] - forward[1] * camera.distance,
+            target[2] - forward[2] * camera.distance,
+        ];
+        let width = camera.width as f32;
+        let height = camera.height as f32;
+        let tangent = (FIELD_OF_VIEW / 2.).tan();
+        Self {
+            eye,
+            right,
+            up,
+            forward,
+            scale: height / 2. / tangent,
+            width,
+            height,
+        }
+    }
+
+    fn depth(&self, position: &RenderPosition) -> f32 {
+        dot(sub(*position, self.eye), self.forward)
+    }
+
+    // Pixel x and y and the depth. Positions behind the near plane are clipped before
+    fn project(&self, position: &RenderPosition) -> [f32; 3] {
+        let relative = sub(*position, self.eye);
+        let depth = dot(relative, self.forward).max(NEAR);
+        [
+            self.width / 2. + dot(relative, self.right) / depth * self.scale,
+            self.height / 2. - dot(relative, self.up) / depth * self.scale,
+            depth,
+        ]
+    }
+
+    // The part of the triangle in front of the near plane: none, 3 or 4 corners in the same order
+    fn clip(&self, positions: &[RenderPosition]) -> Vec<RenderPosition> {
+        let mut clipped = Vec::with_capacity(4);
+        for (index, position) in positions.iter().enumerate() {
+            let next = &positions[(index + 1) % positions.len()];
+            let (depth, next_depth) = (self.depth(position), self.depth(next));
+            if depth >= NEAR {
+                clipped.push(*position);
+            }
+            if (depth >= NEAR) != (next_depth >= NEAR) {
+                let share = (NEAR - depth) / (next_depth - depth);
+                clipped.push(
+                    [0, 1, 2].map(|axis| position[axis] + (next[axis] - position[axis]) * share),
+                );
+            }
+        }
+        clipped
+    }
+
+    // Sky above the horizon, ground below it
+    fn background(&self, x: f32, y: f32) -> [u8; 3] {
+        let ray_x = (x - self.width / 2.) / self.scale;
+        let ray_y = (self.height / 2. - y) / self.scale;
+        let ray_up = self.forward[1] + self.right[1] * ray_x + self.up[1] * ray_y;
+        if ray_up < 0. && self.eye[1] > 0. {
+            GROUND_COLOR
+        } else {
+            SKY_COLOR
+        }
+    }
+}
+
+pub fn render_snapshot(osm_meshes: &[OsmMeshAttributes], camera: &SnapshotCamera) -> Snapshot {
+    let view = View::new(camera, center_of(osm_meshes));
+    let width = camera.width as usize;
+    let height = camera.height as usize;
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let [r, g, b] = view.background(x as f32 + 0.5, y as f32 + 0.5);
+            pixels.extend([r, g, b, 255]);
+        }
+    }
+    let mut depths = vec![f32::INFINITY; width * height];
+
+    for mesh in osm_meshes {
+        for triangle in mesh.indices_to_vertices.chunks_exact(3) {
+            let corners = [0, 1, 2].map(|corner| triangle[corner] as usize);
+            let Some(positions) = corners
+                .iter()
+                .map(|index| mesh.vertices_positions.get(*index).copied())
+                .collect::<Option<Vec<RenderPosition>>>()
+            else {
+                continue;
+            };
+            let projected: Vec<[f32; 3]> = view
+                .clip(&positions)
+                .iter()
+                .map(|position| view.project(position))
+                .collect();
+            if projected.len() < 3 {
+                continue;
+            }
+            let color = flat_color(mesh, &corners, &positions);
+            // A fan of the clipped corners
+            for index in 1..projected.len() - 1 {
+                let fan = [projected[0], projected[index], projected[index + 1]];
+                draw_triangle(&fan, color, width, height, &mut depths, &mut pixels);
+            }
+        }
+    }
+
+    Snapshot {
+        width: camera.width,
+        height: camera.height,
+        pixels,
+    }
+}
+
+// The average colour of the corners, lit by the sun
+fn flat_color(
+    mesh: &OsmMeshAttributes,
+    corners: &[usize; 3],
+    positions: &[RenderPosition],
+) -> [u8; 3] {
+    let normal = normalize(cross(
+        sub(positions[1], positions[0]),
+        sub(positions[2], positions[0]),
+    ));
+    let light = AMBIENT + (1. - AMBIENT) * dot(normal, TO_LIGHT).max(0.);
+    let mut color: RenderColor = [0.; 4];
+    for corner in corners {
+        let corner_color = mesh.vertices_colors.get(*corner).unwrap_or(&[1.; 4]);
+        for (channel, value) in color.iter_mut().zip(corner_color) {
+            *channel += value / 3.;
+        }
+    }
+    [0, 1, 2].map(|channel| to_srgb(color[channel] * light))
+}
+
+fn draw_triangle(
+    projected: &[[f32; 3]],
+    color: [u8; 3],
+    width: usize,
+    height: usize,
+    depths: &mut [f32],
+    pixels: &mut [u8],
+) {
+    let [a, b, c] = [projected[0], projected[1], projected[2]];
+    // Counter clockwise seen from the front is clockwise on the screen, y is down
+    let area = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+    if area >= 0. {
+        return;
+    }
+    let min_x = a[0].min(b[0]).min(c[0]).floor().max(0.) as usize;
+    let max_x = (a[0].max(b[0]).max(c[0]).ceil().max(0.) as usize).min(width);
+    let min_y = a[1].min(b[1]).min(c[1]).floor().max(0.) as usize;
+    let max_y = (a[1].max(b[1]).max(c[1]).ceil().max(0.) as usize).min(height);
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let point = [x as f32 + 0.5, y as f32 + 0.5];
+            let edge = |from: [f32; 3], to: [f32; 3]| {
+                (to[0] - from[0]) * (point[1] - from[1]) - (to[1] - from[1]) * (point[0] - from[0])
+            };
+            // Shares of the corners, all of them positive inside
+            let share_a = edge(b, c) / area;
+            let share_b = edge(c, a) / area;
+            let share_c = edge(a, b) / area;
+            if share_a < 0. || share_b < 0. || share_c < 0. {
+                continue;
+            }
+            // The inverse depth is linear on the screen
+            let depth = 1. / (share_a / a[2] + share_b / b[2] + share_c / c[2]);
+            let index = y * width + x;
+            if depth < depths[index] {
+                depths[index] = depth;
+                pixels[index * 4..index * 4 + 3].copy_from_slice(&color);
+            }
+        }
+    }
+}
+
+// The center of the bounding box of all vertices
+fn center_of(osm_meshes: &[OsmMeshAttributes]) -> [f32; 3] {
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+    for position in osm_meshes
+        .iter()
+        .flat_map(|mesh| mesh.vertices_positions.iter())
+    {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(position[axis]);
+            max[axis] = max[axis].max(position[axis]);
+        }
+    }
+    if min[0] > max[0] {
+        return [0.; 3];
+    }
+    [0, 1, 2].map(|axis| (min[axis] + max[axis]) / 2.)
+}
+
+// The vertex colours are linear, like Bevy takes them. The PNG is sRGB
+fn to_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0., 1.);
+    let srgb = if linear <= 0.003_130_8 {
+        linear * 12.92
+    } else {
+        1.055 * linear.powf(1. / 2.4) - 0.055
+    };
+    (srgb * 255.).round() as u8
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> [f32; 3] {
+    let length = dot(a, a).sqrt();
+    if length > 0. {
+        [a[0] / length, a[1] / length, a[2] / length]
+    } else {
+        a
+    }
+}
@@ -0,0 +1,257 @@
+///////////////////////////////////////////////////////////////////////////////////////////////////
+// 2D plan view of the buildings and parts as SVG, for quick reviews and changeset comments ///////
+
+use std::fmt::Write;
+
+use geo::{BooleanOps, BoundingRect, Euclidean, Length, LineString, MultiLineString, Rect};
+
+use crate::kernel_in::{BuildingOrPart, BuildingsAndParts, GroundPosition, RenderColor, RoofShape};
+
+static MARGIN: f64 = 5.; // meters around the buildings
+static LOW_COLOR: RenderColor = [0.95, 0.95, 0.65, 1.0];
+static HIGH_COLOR: RenderColor = [0.7, 0.1, 0.1, 1.0];
+static OUTSIDE_COLOR: &str = "#ff0000";
+static ARROW_SIZE: f64 = 1.5; // meters
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PlanColoring {
+    #[default]
+    ByHeight, // low light yellow to high dark red
+    ByRoofColor,
+}
+
+// Options of the 2D renderer
+#[derive(Clone, Copy, Debug)]
+pub struct PlanOptions {
+    pub coloring: PlanColoring,
+    pub pixels_per_meter: f64,
+    pub labels: bool, // the OSM ids
+    pub ridges: bool, // ridges of gabled roofs, the slope of skillion roofs
+}
+
+impl Default for PlanOptions {
+    fn default() -> Self {
+        Self {
+            coloring: PlanColoring::default(),
+            pixels_per_meter: 4.,
+            labels: true,
+            ridges: true,
+        }
+    }
+}
+
+pub fn scan_elements_from_layer_to_svg(buildings_and_parts: &BuildingsAndParts) -> String {
+    scan_elements_from_layer_to_svg_with_options(buildings_and_parts, &PlanOptions::default())
+}
+
+/*
+ * Seen from above, north up. Buildings first, then the parts from low to high.
+ * Parts, reaching out of their building or in none, have a red dashed outline.
+ */
+pub fn scan_elements_from_layer_to_svg_with_options(
+    buildings_and_parts: &BuildingsAndParts,
+    options: &PlanOptions,
+) -> String {
+    let Some(bounding_box) = bounding_box_of(buildings_and_parts) else {
+        return r#"<svg xmlns="http://www.w3.org/2000/svg"/>"#.into();
+    };
+    let scale = options.pixels_per_meter;
+    let west = bounding_box.min().x - MARGIN;
+    let north = bounding_box.max().y + MARGIN;
+    // SVG y is down, OSM north is up
+    let to_svg =
+        |position: &GroundPosition| ((position.x - west) * scale, (north - position.y) * scale);
+
+    let highest = buildings_and_parts
+        .iter()
+        .map(top_height)
+        .fold(0., f64::max);
+
+    let mut ordered: Vec<&BuildingOrPart> = buildings_and_parts.iter().collect();
+    ordered.sort_by(|a, b| {
+        a.part
+            .cmp(&b.part)
+            .then(top_height(a).total_cmp(&top_height(b)))
+    });
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{:.0}" height="{:.0}" font-family="sans-serif" font-size="10">"#,
+        (bounding_box.width() + MARGIN * 2.) * scale,
+        (bounding_box.height() + MARGIN * 2.) * scale,
+    );
+
+    for building_or_part in &ordered {
+        let color = match options.coloring {
+            PlanColoring::ByHeight => height_color(top_height(building_or_part), highest),
+            PlanColoring::ByRoofColor => building_or_part.roof_color,
+        };
+        let outside = building_or_part.coverage.outside_share() > 0.;
+        let stroke = if outside {
+            format!(r#"stroke="{OUTSIDE_COLOR}" stroke-width="2" stroke-dasharray="6 3""#)
+        } else {
+            r#"stroke="black" stroke-width="1""#.into()
+        };
+
+        let mut path = String::new();
+        for polygon in building_or_part.footprint.multipolygon.iter() {
+            for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+                path += &ring_path(ring, &to_svg);
+            }
+        }
+        let _ = writeln!(
+            svg,
+            r#"<path d="{path}" fill="{}" fill-rule="evenodd" {stroke}><title>{} {}</title></path>"#,
+            svg_color(&color),
+            if building_or_part.part {
+                "part"
+            } else {
+                "building"
+            },
+            building_or_part.id,
+        );
+
+        if options.ridges {
+            svg += &ridge_line(building_or_part, &to_svg);
+        }
+    }
+
+    // Labels last, not covered by other parts
+    if options.labels {
+        for building_or_part in &ordered {
+            let (x, y) = to_svg(&building_or_part.footprint.center);
+            let outside_share = building_or_part.coverage.outside_share();
+            let text = if outside_share > 0. {
+                format!(
+                    r#"<tspan fill="{OUTSIDE_COLOR}">{} ({:.0}% outside)</tspan>"#,
+                    building_or_part.id,
+                    outside_share * 100.
+                )
+            } else {
+                building_or_part.id.to_string()
+            };
+            let _ = writeln!(
+                svg,
+                r#"<text x="{x:.1}" y="{y:.1}" text-anchor="middle">{text}</text>"#
+            );
+        }
+    }
+
+    svg += "</svg>\n";
+    svg
+}
+
+fn bounding_box_of(buildings_and_parts: &BuildingsAndParts) -> Option<Rect> {
+    buildings_and_parts
+        .iter()
+        .filter_map(|building_or_part| building_or_part.footprint.multipolygon.bounding_rect())
+        .reduce(|a, b| {
+            Rect::new(
+                GroundPosition {
+                    x: a.min().x.min(b.min().x),
+                    y: a.min().y.min(b.min().y),
+                },
+                GroundPosition {
+                    x: a.max().x.max(b.max().x),
+                    y: a.max().y.max(b.max().y),
+                },
+            )
+        })
+}
+
+fn top_height(building_or_part: &BuildingOrPart) -> f64 {
+    building_or_part.wall_height + building_or_part.roof_height
+}
+
+fn ring_path(ring: &LineString, to_svg: &impl Fn(&GroundPosition) -> (f64, f64)) -> String {
+    let mut path = String::new();
+    for (index, position) in ring.coords().enumerate() {
+        let (x, y) = to_svg(position);
+        let command = if index == 0 { 'M' } else { 'L' };
+        let _ = write!(path, "{command}{x:.1},{y:.1} ");
+    }
+    path + "Z "
+}
+
+/*
+ * Gabled roofs: the ridge, across the roof direction.
+ * Skillion roofs: an arrow down the slope, along the roof direction
+ */
+fn ridge_line(
+    building_or_part: &BuildingOrPart,
+    to_svg: &impl Fn(&GroundPosition) -> (f64, f64),
+) -> String {
+    let footprint = &building_or_part.footprint;
+    let angle = match building_or_part.roof_shape {
+        RoofShape::Gabled => building_or_part.roof_angle + f64::to_radians(90.),
+        RoofShape::Skillion => building_or_part.roof_angle,
+        _ => return String::new(),
+    };
+    let direction = GroundPosition {
+        x: angle.sin(),
+        y: angle.cos(),
+    };
+    // The longest piece of the line through the center, inside the footprint
+    let (min, max) = footprint.extent_along(angle);
+    let through = MultiLineString::new(vec![LineString::new(vec![
+        footprint.center + direction * min,
+        footprint.center + direction * max,
+    ])]);
+    let Some(piece) = footprint
+        .multipolygon
+        .clip(&through, false)
+        .into_iter()
+        .max_by(|a, b| Euclidean.length(a).total_cmp(&Euclidean.length(b)))
+    else {
+        return String::new();
+    };
+    let (Some(first), Some(last)) = (piece.0.first(), piece.0.last()) else {
+        return String::new();
+    };
+    // The arrow points along the direction
+    let along = *last - *first;
+    let (start, end) = if along.x * direction.x + along.y * direction.y >= 0. {
+        (*first, *last)
+    } else {
+        (*last, *first)
+    };
+    let (x1, y1) = to_svg(&start);
+    let (x2, y2) = to_svg(&end);
+    let mut line = format!(
+        r#"<line x1="{x1:.1}" y1="{y1:.1}" x2="{x2:.1}" y2="{y2:.1}" stroke="black" stroke-width="1.5"/>"#
+    );
+    if let RoofShape::Skillion = building_or_part.roof_shape {
+        let across = GroundPosition {
+            x: direction.y,
+            y: -direction.x,
+        };
+        let back = end - direction * ARROW_SIZE;
+        let (x3, y3) = to_svg(&(back + across * (ARROW_SIZE / 2.)));
+        let (x4, y4) = to_svg(&(back - across * (ARROW_SIZE / 2.)));
+        let _ = write!(
+            line,
+            r#"<path d="M{x3:.1},{y3:.1} L{x2:.1},{y2:.1} L{x4:.1},{y4:.1}" fill="none" stroke="black" stroke-width="1.5"/>"#
+        );
+    }
+    line + "\n"
+}
+
+fn height_color(height: f64, highest: f64) -> RenderColor {
+    let share = if highest > 0. {
+        (height / highest) as f32
+    } else {
+        0.
+    };
+    let mut color = LOW_COLOR;
+    for (channel, high) in color.iter_mut().zip(HIGH_COLOR) {
+        *channel += (high - *channel) * share;
+    }
+    color
+}
+
+fn svg_color(color: &RenderColor) -> String {
+    let [r, g, b] =
+        [color[0], color[1], color[2]].map(|channel| (channel.clamp(0., 1.) * 255.) as u8);
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
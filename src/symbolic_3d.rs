@@ -1,10 +1,17 @@
 // geo primitives
-use geo::{Coord, LineString, Point, Rotate, TriangulateEarcut}; // Triangle
+use geo::{Coord, LineString, Point, Rotate, TriangulateEarcut, Winding}; // Triangle
 //use VecDeque::pop_front;
 
+use crate::elevation::Elevation;
+use crate::facade::facade_quads;
 use crate::footprint::Footprint;
-use crate::kernel_in::{BuildingOrPart, BuildingsAndParts, GroundPosition, RoofShape};
+use crate::ground::{CROWN_COLOR, TRUNK_COLOR};
+use crate::kernel_in::{
+    BuildingOrPart, BuildingsAndParts, GeographicCoordinates, GroundFeatures, GroundPosition,
+    GroundPositions, GroundShape, RoofShape, Structure,
+};
 use crate::kernel_out::{OsmMeshAttributes, RenderColor, RenderPosition, RenderPositions};
+use crate::projection::LocalProjection;
 
 ///////////////////////////////////////////////////////////////////////////////////////////////////
 // OSM ////////////////////////////////////////////////////////////////////////////////////////////
@@ -13,12 +20,35 @@ use crate::kernel_out::{OsmMeshAttributes, RenderColor, RenderPosition, RenderPo
 static MULTI_MESH: bool = false;
 static _GPU_POSITION_NULL: RenderPosition = [0.0, 0.0, 0.0];
 static O: usize = 0; // Just to silent lint, make some lines equal and to show, the Offset may also be 0
+static ROOF_THICKNESS: f64 = 0.3; // of a roof only building or a thin roof
+static PILLAR_SIZE: f64 = 0.3;
+static MAX_PILLARS: usize = 8;
+static TERRAIN_CELLS: usize = 100; // per side
+static TREE_TRUNK_SIZE: f64 = 0.4;
+static TREE_CROWN_START: f64 = 0.35; // share of the tree height
+
+// Options of the 3D renderer (may be more later)
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderOptions {
+    pub facade: bool, // windows and level lines on the walls
+}
 
 // Local methodes of GroundPosition, only to be used in the renderer!
 pub fn to_gpu_position(coord: &Coord, height: f64) -> RenderPosition {
     [coord.x as f32, height as f32, -coord.y as f32] // -y bedause: OSM +nord => GPU -Z
 }
 
+// The inverse of to_gpu_position: the ground position and the height. See position_to_coordinates
+pub fn from_gpu_position(position: &RenderPosition) -> (GroundPosition, f64) {
+    (
+        GroundPosition {
+            x: position[0] as f64,
+            y: -position[2] as f64,
+        },
+        position[1] as f64,
+    )
+}
+
 impl Footprint {
     fn _get_gpu_positions(&self, height: f64) -> RenderPositions {
         let mut roof_gpu_positions: RenderPositions = Vec::new();
@@ -42,19 +72,33 @@ impl Footprint {
 
 pub fn scan_elements_from_layer_to_mesh(
     buildings_and_parts: BuildingsAndParts,
+) -> Vec<OsmMeshAttributes> {
+    scan_elements_from_layer_to_mesh_with_options(buildings_and_parts, &RenderOptions::default())
+}
+
+pub fn scan_elements_from_layer_to_mesh_with_options(
+    buildings_and_parts: BuildingsAndParts,
+    options: &RenderOptions,
 ) -> Vec<OsmMeshAttributes> {
     let mut osm_attributs = Vec::new();
 
-    let mut osm_mesh = OsmMesh::new();
+    let mut osm_mesh = OsmMesh::new(*options);
     for mut building_or_part in buildings_and_parts {
         osm_mesh.prepare_roof(&building_or_part);
 
+        let first_vertex = osm_mesh.attributes.vertices_positions.len();
         osm_mesh.push_building_or_part(&mut building_or_part);
+        // Raise the building onto the terrain
+        if building_or_part.ground_height != 0. {
+            for position in &mut osm_mesh.attributes.vertices_positions[first_vertex..] {
+                position[1] += building_or_part.ground_height as f32;
+            }
+        }
 
         if MULTI_MESH {
             //println!("MULTI_MESH");
             osm_attributs.push(osm_mesh.attributes);
-            osm_mesh = OsmMesh::new();
+            osm_mesh = OsmMesh::new(*options);
         }
     }
 
@@ -65,16 +109,104 @@ pub fn scan_elements_from_layer_to_mesh(
     osm_attributs
 }
 
+/*
+ * The terrain around the GPU 0 position as a grid of triangles. It replaces the flat ground.
+ * Outside the elevation tiles, the terrain is at height 0
+ */
+pub fn terrain_to_mesh(
+    elevation: &Elevation,
+    gpu_ground_null_coordinates: &GeographicCoordinates,
+    range: f64,
+    color: RenderColor,
+) -> OsmMeshAttributes {
+    let mut attributes = OsmMeshAttributes::new();
+    let projection = LocalProjection::new(*gpu_ground_null_coordinates);
+    let step = range * 2. / TERRAIN_CELLS as f64;
+    for row in 0..=TERRAIN_CELLS {
+        for column in 0..=TERRAIN_CELLS {
+            let position = GroundPosition {
+                x: -range + column as f64 * step,
+                y: -range + row as f64 * step,
+            };
+            let coordinates = projection.inverse(position);
+            let height = elevation
+                .height_above_origin(gpu_ground_null_coordinates, &coordinates)
+                .unwrap_or(0.);
+            attributes
+                .vertices_positions
+                .push(to_gpu_position(&position, height));
+            attributes.vertices_colors.push(color);
+        }
+    }
+    let columns = TERRAIN_CELLS as u32 + 1;
+    for row in 0..TERRAIN_CELLS as u32 {
+        for column in 0..TERRAIN_CELLS as u32 {
+            let south_west = row * columns + column;
+            let north_west = south_west + columns;
+            attributes.indices_to_vertices.extend([
+                south_west,
+                south_west + 1,
+                north_west + 1,
+                south_west,
+                north_west + 1,
+                north_west,
+            ]);
+        }
+    }
+    attributes
+}
+
+// The roads, water, landuse and trees around the buildings, as one mesh
+pub fn ground_to_mesh(ground_features: &GroundFeatures) -> OsmMeshAttributes {
+    let mut osm_mesh = OsmMesh::new(RenderOptions::default());
+    for feature in ground_features {
+        match &feature.shape {
+            GroundShape::Area(footprint) => {
+                osm_mesh.push_flat(&mut footprint.clone(), feature.level, false, feature.color)
+            }
+            GroundShape::Line { positions, width } => {
+                osm_mesh.push_ribbon(positions, *width, feature.level, feature.color)
+            }
+            GroundShape::Tree {
+                position,
+                height,
+                crown_diameter,
+            } => osm_mesh.push_tree(position, *height, *crown_diameter),
+        }
+    }
+    osm_mesh.attributes
+}
+
+// Raise each vertex of a flat mesh by the terrain below it
+pub fn lift_onto_terrain(
+    mesh: &mut OsmMeshAttributes,
+    elevation: &Elevation,
+    gpu_ground_null_coordinates: &GeographicCoordinates,
+) {
+    let projection = LocalProjection::new(*gpu_ground_null_coordinates);
+    for position in &mut mesh.vertices_positions {
+        let (ground_position, _height) = from_gpu_position(position);
+        let coordinates = projection.inverse(ground_position);
+        if let Some(height) =
+            elevation.height_above_origin(gpu_ground_null_coordinates, &coordinates)
+        {
+            position[1] += height as f32;
+        }
+    }
+}
+
 // Methode-Extenton of the "CLASS" OSM-Mesh, only needed internaly here ////////////////////////
 #[derive(Clone, Debug)]
 struct OsmMesh {
     attributes: OsmMeshAttributes,
+    options: RenderOptions,
 }
 
 impl OsmMesh {
-    fn new() -> Self {
+    fn new(options: RenderOptions) -> Self {
         OsmMesh {
             attributes: OsmMeshAttributes::new(),
+            options,
         }
     }
 
@@ -89,6 +221,11 @@ impl OsmMesh {
         let color = building_or_part.building_color;
         let roof_color = building_or_part.roof_color;
 
+        if let Structure::Steps { count } = building_or_part.structure {
+            self.push_steps(building_or_part, count, color);
+            return;
+        }
+
         match building_or_part.roof_shape {
             //
             RoofShape::Skillion => {
@@ -121,13 +258,196 @@ impl OsmMesh {
                 roof_color,
             ),
             / **/
-            _ => self.push_flat(&mut building_or_part.footprint, wall_height, roof_color),
+            _ => self.push_flat(
+                &mut building_or_part.footprint,
+                wall_height,
+                false,
+                roof_color,
+            ),
+        }
+
+        match building_or_part.structure {
+            Structure::RoofOnly => {
+                self.push_roof_only(building_or_part, roof_color, color);
+                return;
+            }
+            Structure::ThinRoof => {
+                let roof_bottom = building_or_part.wall_height - ROOF_THICKNESS;
+                self.push_roof_slab(building_or_part, roof_bottom, roof_color);
+                return;
+            }
+            _ => (),
         }
 
         self.push_walls(building_or_part, min_height, color);
 
-        if min_height > 0.0 {
-            self.push_flat(&mut building_or_part.footprint, -min_height, roof_color);
+        // Floating parts and volumes below the ground need a bottom
+        if min_height != 0.0 {
+            self.push_flat(
+                &mut building_or_part.footprint,
+                min_height,
+                true,
+                roof_color,
+            );
+        }
+    }
+
+    // Steps: Flat blocks, each one step higher, rising against the roof direction
+    fn push_steps(&mut self, building_or_part: &BuildingOrPart, count: usize, color: RenderColor) {
+        let min_height = building_or_part.min_height;
+        let angle = building_or_part.roof_angle;
+        let (low, up) = building_or_part.footprint.extent_along(angle);
+        let depth = (up - low) / count as f64;
+        let rise = (building_or_part.wall_height - min_height) / count as f64;
+
+        for step in 0..count {
+            let mut block = building_or_part.clone();
+            block.roof_shape = RoofShape::Flat;
+            block.footprint.is_circular = false;
+            block.wall_height = min_height + rise * (step + 1) as f64;
+            block.footprint.multipolygon = building_or_part.footprint.slice_along(
+                angle,
+                up - depth * (step + 1) as f64,
+                up - depth * step as f64,
+            );
+            let wall_height = block.wall_height;
+            self.push_flat(&mut block.footprint, wall_height, false, color);
+            self.push_walls(&mut block, min_height, color);
+        }
+
+        if min_height != 0.0 {
+            let mut footprint = building_or_part.footprint.clone();
+            self.push_flat(&mut footprint, min_height, true, color);
+        }
+    }
+
+    // A roof on pillars: The roof gets an edge and a bottom, the walls are replaced by pillars
+    fn push_roof_only(
+        &mut self,
+        building_or_part: &mut BuildingOrPart,
+        roof_color: RenderColor,
+        color: RenderColor,
+    ) {
+        let min_height = building_or_part.min_height;
+        let roof_bottom = (building_or_part.wall_height - ROOF_THICKNESS).max(min_height);
+        self.push_roof_slab(building_or_part, roof_bottom, roof_color);
+
+        let center = building_or_part.footprint.center;
+        for polygon in building_or_part.footprint.multipolygon.iter() {
+            let corners: Vec<&GroundPosition> = polygon.exterior().coords().skip(1).collect();
+            let every = corners.len().div_ceil(MAX_PILLARS).max(1);
+            for corner in corners.iter().step_by(every) {
+                // Moved a bit inside, not to stick out of the roof
+                let inward = center - **corner;
+                let distance = (inward.x * inward.x + inward.y * inward.y).sqrt();
+                if distance < PILLAR_SIZE * 2. {
+                    continue;
+                }
+                let position = **corner + inward / distance * PILLAR_SIZE;
+                self.push_pillar(&position, min_height, roof_bottom, color);
+            }
+        }
+    }
+
+    // The edge and the bottom of a roof without walls. The roof itself is pushed already
+    fn push_roof_slab(
+        &mut self,
+        building_or_part: &mut BuildingOrPart,
+        roof_bottom: f64,
+        roof_color: RenderColor,
+    ) {
+        self.push_walls(building_or_part, roof_bottom, roof_color);
+        self.push_flat(
+            &mut building_or_part.footprint,
+            roof_bottom,
+            true,
+            roof_color,
+        );
+    }
+
+    fn push_pillar(
+        &mut self,
+        position: &GroundPosition,
+        bottom: f64,
+        top: f64,
+        color: RenderColor,
+    ) {
+        self.push_column(position, PILLAR_SIZE, bottom, top, color);
+    }
+
+    // The four sides of a square column, without top and bottom
+    fn push_column(
+        &mut self,
+        position: &GroundPosition,
+        size: f64,
+        bottom: f64,
+        top: f64,
+        color: RenderColor,
+    ) -> [GroundPosition; 4] {
+        let half = size / 2.;
+        // Counter clockwise seen from above, so the sides face outwards
+        let corners = [
+            *position + GroundPosition { x: -half, y: -half },
+            *position + GroundPosition { x: half, y: -half },
+            *position + GroundPosition { x: half, y: half },
+            *position + GroundPosition { x: -half, y: half },
+        ];
+        for index in 0..corners.len() {
+            let left = &corners[index];
+            let right = &corners[(index + 1) % corners.len()];
+            self.push_square(
+                to_gpu_position(left, bottom),
+                to_gpu_position(right, bottom),
+                to_gpu_position(left, top),
+                to_gpu_position(right, top),
+                color,
+            );
+        }
+        corners
+    }
+
+    // A natural=tree: a trunk and a square crown above
+    fn push_tree(&mut self, position: &GroundPosition, height: f64, crown_diameter: f64) {
+        let crown_bottom = height * TREE_CROWN_START;
+        self.push_column(position, TREE_TRUNK_SIZE, 0., crown_bottom, TRUNK_COLOR);
+        let [south_west, south_east, north_east, north_west] =
+            self.push_column(position, crown_diameter, crown_bottom, height, CROWN_COLOR);
+        self.push_square(
+            to_gpu_position(&south_west, height),
+            to_gpu_position(&south_east, height),
+            to_gpu_position(&north_west, height),
+            to_gpu_position(&north_east, height),
+            CROWN_COLOR,
+        );
+    }
+
+    // A road or river as a flat ribbon of the width, one square per segment
+    fn push_ribbon(
+        &mut self,
+        positions: &GroundPositions,
+        width: f64,
+        level: f64,
+        color: RenderColor,
+    ) {
+        for segment in positions.windows(2) {
+            let (start, end) = (segment[0], segment[1]);
+            let direction = end - start;
+            let length = direction.x.hypot(direction.y);
+            if length == 0. {
+                continue;
+            }
+            // To the right of the direction
+            let side = GroundPosition {
+                x: direction.y / length * width / 2.,
+                y: -direction.x / length * width / 2.,
+            };
+            self.push_square(
+                to_gpu_position(&(start - side), level),
+                to_gpu_position(&(start + side), level),
+                to_gpu_position(&(end - side), level),
+                to_gpu_position(&(end + side), level),
+                color,
+            );
         }
     }
 
@@ -157,7 +477,7 @@ impl OsmMesh {
         building_or_part.wall_height + building_or_part.roof_height
             - f64::abs(position_rotated.y() - rotated_footprint_south) * inclination
     }
-    /** /
+    /* /
 
     fn calc_gabled_position_height(
         &mut self,
@@ -191,7 +511,13 @@ impl OsmMesh {
     }
 
     // todo: use skilleon with height = constant
-    fn push_flat(&mut self, footprint: &mut Footprint, height: f64, color: RenderColor) {
+    fn push_flat(
+        &mut self,
+        footprint: &mut Footprint,
+        height: f64,
+        facing_down: bool,
+        color: RenderColor,
+    ) {
         // Why not do it with multipolygon:
         // A) the redundant, way ends get unused pushed indices
         // B) there is no earcut_triangles_raw for multipolygon
@@ -209,12 +535,13 @@ impl OsmMesh {
             for i in 0..max {
                 let x = vertices[i * VALUES_PER_COORDINATE + O];
                 let y = vertices[i * VALUES_PER_COORDINATE + 1];
-                let gpu = [x as f32, height.abs() as f32, -y as f32]; // -y bedause: OSM +nord => GPU -Z
+                let gpu = [x as f32, height as f32, -y as f32]; // -y bedause: OSM +nord => GPU -Z
                 self.attributes.vertices_positions.push(gpu);
                 self.attributes.vertices_colors.push(color);
             }
 
-            if height < 0.0 {
+            // The bottom of a floating or underground part is seen from below
+            if facing_down {
                 triangles.triangle_indices.reverse();
             }
 
@@ -252,7 +579,7 @@ impl OsmMesh {
         /* */
     }
 
-    /** /
+    /* /
     fn push_gabled(&mut self, building_or_part: &mut BuildingOrPart, color: RenderColor) {
         let (face1, face2) = building_or_part
             .footprint
@@ -551,6 +878,7 @@ impl OsmMesh {
                 building_or_part,
                 outer,
                 building_or_part.footprint.is_circular,
+                false,
                 min_height,
                 color,
             );
@@ -560,6 +888,7 @@ impl OsmMesh {
                     building_or_part,
                     hole,
                     footprint.is_circular,
+                    true,
                     min_height,
                     color,
                 );
@@ -572,6 +901,7 @@ impl OsmMesh {
         building_or_part: &mut BuildingOrPart,
         wall: &LineString,
         is_circular: bool,
+        is_hole: bool,
         min_height: f64,
         color: RenderColor,
     ) {
@@ -579,6 +909,10 @@ impl OsmMesh {
         let mut to_last_index = (wall.coords().count() * 2 - 2) as isize;
         let mut last_gpu_position_down: [f32; 3] = [0.; 3];
         let mut last_gpu_position_up: [f32; 3] = [0.; 3];
+        let mut last_position = GroundPosition::zero();
+        let mut last_height: f64 = 0.;
+        // The right side of a counterclockwise outer is outside. Holes are the other way round
+        let outward_sign = if wall.is_ccw() != is_hole { 1. } else { -1. };
 
         for (index, position) in wall.coords().enumerate() {
             let height = self.calc_roof_position_height(position, building_or_part);
@@ -603,11 +937,53 @@ impl OsmMesh {
                     );
                 }
                 to_last_index = -2;
+
+                if self.options.facade {
+                    self.push_facade(
+                        building_or_part,
+                        &last_position,
+                        position,
+                        outward_sign,
+                        last_height.min(height),
+                    );
+                }
             }
 
             // Roof Points for triangulation and Onion, Positions for a Phyramide
             last_gpu_position_down = this_gpu_position_down;
             last_gpu_position_up = this_gpu_position_up;
+            last_position = *position;
+            last_height = height;
+        }
+    }
+
+    fn push_facade(
+        &mut self,
+        building_or_part: &BuildingOrPart,
+        start: &GroundPosition,
+        end: &GroundPosition,
+        outward_sign: f64,
+        top: f64,
+    ) {
+        let along = *end - *start;
+        let length = (along.x * along.x + along.y * along.y).sqrt();
+        if length == 0. {
+            return;
+        }
+        // Right of the wall direction, turned to the outside
+        let outward = GroundPosition {
+            x: along.y / length * outward_sign,
+            y: -along.x / length * outward_sign,
+        };
+
+        for quad in facade_quads(start, end, outward, building_or_part, top) {
+            self.push_square(
+                quad.down_left,
+                quad.down_right,
+                quad.up_left,
+                quad.up_right,
+                quad.color,
+            );
         }
     }
 
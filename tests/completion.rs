@@ -0,0 +1,137 @@
+// Completion of the loaded data by a mock API: the missing nodes are loaded and sorted in
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+
+use osm_tb::{
+    BoundingBox, GeographicCoordinates, GroundPosition, GroundShape, InputOsm, JsonData,
+    missing_elements_json,
+};
+
+// The bbox has only the south nodes of the building, the north ones are loaded by completion
+static MAP: &str = r#"{"elements":[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0,"version":1},
+    {"type":"node","id":2,"lat":49.0,"lon":11.0002,"version":1},
+    {"type":"way","id":10,"nodes":[1,2,3,4,1],"version":1,"tags":{"building":"yes"}}
+]}"#;
+static NODES: &str = r#"{"elements":[
+    {"type":"node","id":3,"lat":49.0002,"lon":11.0002,"version":1},
+    {"type":"node","id":4,"lat":49.0002,"lon":11.0,"version":1}
+]}"#;
+
+// Answers each request by the start of its path, 404 if none fits
+fn mock_api(
+    listener: TcpListener,
+    answers: Vec<(&'static str, &'static str)>,
+    sender: mpsc::Sender<String>,
+) {
+    for stream in listener.incoming() {
+        let mut stream = stream.unwrap();
+        let mut request_line = String::new();
+        BufReader::new(&mut stream)
+            .read_line(&mut request_line)
+            .unwrap();
+        let path = request_line
+            .split(' ')
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+        let answer = answers
+            .iter()
+            .find(|(start, _)| path.starts_with(start))
+            .map(|(_, answer)| *answer);
+        let status = if answer.is_some() {
+            "200 OK"
+        } else {
+            "404 Not Found"
+        };
+        let answer = answer.unwrap_or_default();
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{answer}",
+            answer.len()
+        )
+        .unwrap();
+        if sender.send(path).is_err() {
+            break;
+        }
+    }
+}
+
+#[tokio::test]
+async fn loaded_nodes_complete_the_building() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/api/0.6/", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        mock_api(
+            listener,
+            vec![
+                ("/api/0.6/map.json", MAP),
+                ("/api/0.6/nodes.json?nodes=3,4", NODES),
+            ],
+            sender,
+        )
+    });
+
+    let input = InputOsm::new().with_base_url(&api_url).with_completion(1);
+    let bounding_box = BoundingBox::new(
+        GroundPosition { x: 11., y: 49. },
+        GroundPosition {
+            x: 11.0002,
+            y: 49.0001,
+        },
+    );
+    let bytes = input.load_osm(&bounding_box).await.unwrap();
+    assert_eq!(receiver.try_iter().count(), 2);
+
+    let center = GeographicCoordinates {
+        latitude: 49.0001,
+        longitude: 11.0001,
+    };
+    let buildings_and_parts = input.scan_json_to_osm_vec(&bytes, &center, 0, 0);
+    assert_eq!(buildings_and_parts.len(), 1);
+    assert!(!buildings_and_parts[0].incomplete);
+    let outline = &buildings_and_parts[0].footprint.multipolygon.0[0];
+    assert_eq!(outline.exterior().0.len(), 5);
+}
+
+// A road and a building, both with their middle node 3 outside of the loaded data
+static CUT: &str = r#"{"elements":[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0,"version":1},
+    {"type":"node","id":2,"lat":49.0,"lon":11.0002,"version":1},
+    {"type":"node","id":4,"lat":49.0002,"lon":11.0,"version":1},
+    {"type":"node","id":5,"lat":49.0004,"lon":11.0,"version":1},
+    {"type":"way","id":20,"nodes":[1,2,3,4,5],"version":1,"tags":{"highway":"residential"}},
+    {"type":"way","id":30,"nodes":[6,7,8],"version":1,"tags":{"highway":"service"}},
+    {"type":"way","id":10,"nodes":[1,2,9,4,1],"version":1,"tags":{"building":"yes"}}
+]}"#;
+
+#[test]
+fn only_nodes_of_buildings_are_missing() {
+    let json_bbox_data: JsonData = serde_json::from_str(CUT).unwrap();
+    let missing = missing_elements_json(json_bbox_data);
+    assert_eq!(missing.nodes.into_iter().collect::<Vec<_>>(), [9]);
+    assert!(missing.ways.is_empty());
+}
+
+#[test]
+fn lines_are_cut_at_missing_nodes() {
+    let center = GeographicCoordinates {
+        latitude: 49.0001,
+        longitude: 11.0001,
+    };
+    let (_, ground_features) =
+        InputOsm::new().scan_json_to_layers_vec(CUT.as_bytes(), &center, 0, 0);
+    let pieces: Vec<usize> = ground_features
+        .iter()
+        .filter(|feature| feature.id == 20)
+        .map(|feature| match &feature.shape {
+            GroundShape::Line { positions, .. } => positions.len(),
+            _ => 0,
+        })
+        .collect();
+    assert_eq!(pieces, [2, 2]);
+    assert!(ground_features.iter().all(|feature| feature.id != 30));
+}
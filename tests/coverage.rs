@@ -0,0 +1,165 @@
+// Coverage of a building by its parts: classification, slivers and the remains of the profiles
+
+use geo::{LineString, MultiPolygon, Polygon};
+use osm_tb::{
+    BuildingRemains, CoverageRules, GeographicCoordinates, InputOsm, InterpretationProfile,
+    PartCoverage, classify_part, polygon_width, remove_slivers,
+};
+
+static BUILDING: u64 = 10;
+static PART: u64 = 20;
+
+fn rectangle(west: f64, south: f64, east: f64, north: f64) -> Polygon {
+    Polygon::new(
+        LineString::from(vec![
+            (west, south),
+            (east, south),
+            (east, north),
+            (west, north),
+            (west, south),
+        ]),
+        vec![],
+    )
+}
+
+fn multi(polygon: Polygon) -> MultiPolygon {
+    MultiPolygon::new(vec![polygon])
+}
+
+#[test]
+fn inside_overlapping_and_outside() {
+    let building = multi(rectangle(0., 0., 10., 10.));
+    let rules = CoverageRules::default();
+
+    let inside = classify_part(&building, &multi(rectangle(2., 2., 5., 5.)));
+    assert_eq!(inside, PartCoverage::Inside);
+    assert!(inside.belongs_to_building(&rules));
+
+    // 3/4 of the part inside, covering 30% of the building
+    let overlapping = classify_part(&building, &multi(rectangle(7., 0., 11., 10.)));
+    let PartCoverage::Overlapping {
+        part_share,
+        building_share,
+    } = overlapping
+    else {
+        panic!("not overlapping: {overlapping:?}");
+    };
+    assert!((part_share - 0.75).abs() < 1e-9 && (building_share - 0.3).abs() < 1e-9);
+    assert!(overlapping.belongs_to_building(&rules));
+    let mostly_outside = classify_part(&building, &multi(rectangle(9., 0., 13., 10.)));
+    assert!(!mostly_outside.belongs_to_building(&rules));
+
+    let outside = classify_part(&building, &multi(rectangle(20., 0., 25., 10.)));
+    assert_eq!(outside, PartCoverage::Outside);
+    assert!(!outside.belongs_to_building(&rules));
+    assert_eq!(
+        classify_part(&building, &MultiPolygon::new(vec![])),
+        PartCoverage::Outside
+    );
+}
+
+#[test]
+fn slivers_are_removed() {
+    let rules = CoverageRules::default();
+    let remains = MultiPolygon::new(vec![
+        rectangle(0., 0., 10., 10.),
+        rectangle(20., 0., 20.2, 30.), // a thin line along a part
+        rectangle(40., 0., 40.8, 0.8), // too small
+    ]);
+    let kept = remove_slivers(&remains, &rules);
+    assert_eq!(kept, multi(rectangle(0., 0., 10., 10.)));
+}
+
+#[test]
+fn width_of_thin_diagonal_polygons() {
+    assert!((polygon_width(&rectangle(0., 0., 10., 4.)) - 4.).abs() < 0.5);
+    // 0.4 m wide and 50 m long, in many directions. The grid points may miss it
+    for degrees in (0..=90).step_by(3) {
+        let (sin, cos) = (degrees as f64).to_radians().sin_cos();
+        let (along_x, along_y) = (50. * cos, 50. * sin);
+        let (across_x, across_y) = (-0.2 * sin, 0.2 * cos);
+        let strip = Polygon::new(
+            LineString::from(vec![
+                (-across_x, -across_y),
+                (along_x - across_x, along_y - across_y),
+                (along_x + across_x, along_y + across_y),
+                (across_x, across_y),
+                (-across_x, -across_y),
+            ]),
+            vec![],
+        );
+        let width = polygon_width(&strip);
+        assert!((0.39..=0.41).contains(&width), "{degrees}°: {width}");
+    }
+}
+
+// A building of about 14.6 x 22.2 m, the part covers it from the west to the longitude
+fn building_with_part(part_east: f64) -> String {
+    format!(
+        r#"{{"elements":[
+        {{"type":"node","id":1,"lat":49.0,"lon":11.0}},
+        {{"type":"node","id":2,"lat":49.0,"lon":11.0002}},
+        {{"type":"node","id":3,"lat":49.0002,"lon":11.0002}},
+        {{"type":"node","id":4,"lat":49.0002,"lon":11.0}},
+        {{"type":"node","id":5,"lat":49.0,"lon":{part_east}}},
+        {{"type":"node","id":6,"lat":49.0002,"lon":{part_east}}},
+        {{"type":"way","id":{BUILDING},"nodes":[1,2,3,4,1],"tags":{{"building":"yes"}}}},
+        {{"type":"way","id":{PART},"nodes":[1,5,6,4,1],"tags":{{"building:part":"yes"}}}}
+    ]}}"#
+    )
+}
+
+fn center() -> GeographicCoordinates {
+    GeographicCoordinates {
+        latitude: 49.0001,
+        longitude: 11.0001,
+    }
+}
+
+fn ids(json: &str, profile: InterpretationProfile) -> Vec<u64> {
+    let mut ids: Vec<u64> = InputOsm::new()
+        .with_profile(profile)
+        .scan_json_to_osm_vec(json.as_bytes(), &center(), 0, 0)
+        .iter()
+        .map(|building_or_part| building_or_part.id)
+        .collect();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn building_remains_by_profile() {
+    assert_eq!(
+        InterpretationProfile::Obi.rules().building_remains,
+        BuildingRemains::MinPercent(20)
+    );
+    // Half of the building is left
+    let half = building_with_part(11.0001);
+    assert_eq!(ids(&half, InterpretationProfile::Obi), [BUILDING, PART]);
+    assert_eq!(ids(&half, InterpretationProfile::F4map), [BUILDING, PART]);
+    assert_eq!(ids(&half, InterpretationProfile::S3dbStrict), [PART]);
+    assert_eq!(ids(&half, InterpretationProfile::Osm2World), [PART]);
+
+    // A tenth is left
+    let tenth = building_with_part(11.00018);
+    assert_eq!(ids(&tenth, InterpretationProfile::Obi), [PART]);
+    assert_eq!(ids(&tenth, InterpretationProfile::F4map), [BUILDING, PART]);
+}
+
+#[test]
+fn buildings_without_parts_are_kept() {
+    // A kiosk below 1 m² and a wall-like building, 0.4 m wide and diagonal
+    let json = r#"{"elements":[
+        {"type":"node","id":1,"lat":49.0,"lon":11.0},
+        {"type":"node","id":2,"lat":49.0,"lon":11.00001},
+        {"type":"node","id":3,"lat":49.000008,"lon":11.00001},
+        {"type":"node","id":4,"lat":49.000008,"lon":11.0},
+        {"type":"node","id":11,"lat":49.0001,"lon":11.0001},
+        {"type":"node","id":12,"lat":49.0001,"lon":11.000104},
+        {"type":"node","id":13,"lat":49.0004,"lon":11.000504},
+        {"type":"node","id":14,"lat":49.0004,"lon":11.0005},
+        {"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{"building":"kiosk"}},
+        {"type":"way","id":30,"nodes":[11,12,13,14,11],"tags":{"building":"yes"}}
+    ]}"#;
+    assert_eq!(ids(json, InterpretationProfile::Obi), [10, 30]);
+}
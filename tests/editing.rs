@@ -0,0 +1,219 @@
+// Tag edits on the Reifenberg chapel of assets/way.json, uploaded to a mock API server
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+use osm_tb::{
+    ChangeAction, EditSession, GeographicCoordinates, InputOsm, OsmChange, RenderOptions, TagEdit,
+    UploadClient,
+};
+
+static CHAPEL: u64 = 121486088;
+static TOKEN: &str = "test-token";
+
+fn session() -> EditSession {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    EditSession::new(&std::fs::read(root.join("assets").join("way.json")).unwrap()).unwrap()
+}
+
+fn center() -> GeographicCoordinates {
+    GeographicCoordinates {
+        latitude: 49.756,
+        longitude: 11.136,
+    }
+}
+
+#[test]
+fn parse_tag_edit() {
+    assert_eq!(
+        "121486088:roof:shape=gabled".parse(),
+        Ok(TagEdit {
+            id: CHAPEL,
+            key: "roof:shape".into(),
+            value: Some("gabled".into()),
+        })
+    );
+    let removed: TagEdit = "121486088:height=".parse().unwrap();
+    assert_eq!(removed.value, None);
+    assert!("roof:shape=gabled".parse::<TagEdit>().is_err());
+    assert!("121486088:roof:shape".parse::<TagEdit>().is_err());
+}
+
+#[test]
+fn preview_shows_the_edit() {
+    let mut session = session();
+    let options = RenderOptions::default();
+    let (original, original_meshes) =
+        session.preview(&InputOsm::new(), &center(), 0, CHAPEL, &options);
+    session
+        .edit(format!("{CHAPEL}:height=20").parse().unwrap())
+        .unwrap();
+    let (edited, edited_meshes) = session.preview(&InputOsm::new(), &center(), 0, CHAPEL, &options);
+    assert_eq!(edited.len(), original.len());
+    assert!(edited[0].wall_height > original[0].wall_height);
+    assert_ne!(
+        edited_meshes[0].vertices_positions,
+        original_meshes[0].vertices_positions
+    );
+
+    session.undo();
+    let (undone, _) = session.preview(&InputOsm::new(), &center(), 0, CHAPEL, &options);
+    assert_eq!(undone[0].wall_height, original[0].wall_height);
+}
+
+#[test]
+fn unknown_element() {
+    assert!(session().edit("42:height=20".parse().unwrap()).is_err());
+}
+
+#[test]
+fn osm_change_of_the_edits() {
+    let mut session = session();
+    for edit in [
+        "121486088:roof:shape=gabled",
+        "121486088:roof:colour=red",
+        "121486088:roof:colour=dark_red",
+        "121486088:wheelchair=",
+    ] {
+        session.edit(edit.parse().unwrap()).unwrap();
+    }
+    assert_eq!(session.edits().count(), 3);
+
+    let xml = session.osm_change(77).unwrap();
+    assert!(xml.contains("<way id=\"121486088\" version=\""));
+    assert!(xml.contains("changeset=\"77\""));
+    // Read back by the osmChange reader
+    let osm_change = OsmChange::from_xml(&xml).unwrap();
+    let (action, way) = &osm_change.elements[0];
+    assert_eq!(*action, ChangeAction::Modify);
+    assert_eq!(way["tags"]["roof:shape"], "gabled");
+    assert_eq!(way["tags"]["roof:colour"], "dark_red");
+    assert!(way["tags"].get("wheelchair").is_none());
+    assert_eq!(way["tags"]["building"], "church");
+    assert_eq!(way["nodes"].as_array().unwrap().len(), 16);
+}
+
+// One request: the request line, the Authorization header and the body
+fn read_request(stream: &mut std::net::TcpStream) -> (String, String, String) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).unwrap();
+    let mut authorization = String::new();
+    let mut length = 0;
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':').unwrap();
+        match name.to_lowercase().as_str() {
+            "authorization" => authorization = value.trim().into(),
+            "content-length" => length = value.trim().parse().unwrap(),
+            _ => (),
+        }
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).unwrap();
+    (
+        request_line.trim_end().into(),
+        authorization,
+        String::from_utf8(body).unwrap(),
+    )
+}
+
+// Answers like the API: a changeset id, a diffResult and nothing to the close
+fn mock_api(
+    listener: TcpListener,
+    requests: usize,
+    sender: mpsc::Sender<(String, String, String)>,
+) {
+    for _ in 0..requests {
+        let (mut stream, _) = listener.accept().unwrap();
+        let request = read_request(&mut stream);
+        let answer = if request.0.contains("/changeset/create") {
+            "42"
+        } else if request.0.contains("/upload") {
+            "<diffResult version=\"0.6\"><way old_id=\"121486088\" new_id=\"121486088\" new_version=\"6\"/></diffResult>"
+        } else {
+            ""
+        };
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{answer}",
+            answer.len()
+        )
+        .unwrap();
+        sender.send(request).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn upload_to_mock_api() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/api/0.6", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+    let server = std::thread::spawn(move || mock_api(listener, 3, sender));
+
+    let mut session = session();
+    session
+        .edit("121486088:roof:shape=gabled".parse().unwrap())
+        .unwrap();
+    let changeset_id = UploadClient::new(&api_url, TOKEN)
+        .upload(&session, "Roof of the chapel")
+        .await
+        .unwrap();
+    assert_eq!(changeset_id, 42);
+    server.join().unwrap();
+
+    let requests: Vec<_> = receiver.iter().collect();
+    assert_eq!(requests[0].0, "PUT /api/0.6/changeset/create HTTP/1.1");
+    assert!(requests[0].2.contains("v=\"Roof of the chapel\""));
+    assert_eq!(requests[1].0, "POST /api/0.6/changeset/42/upload HTTP/1.1");
+    assert!(
+        requests[1]
+            .2
+            .contains("<tag k=\"roof:shape\" v=\"gabled\"/>")
+    );
+    assert_eq!(requests[2].0, "PUT /api/0.6/changeset/42/close HTTP/1.1");
+    for (_, authorization, _) in &requests {
+        assert_eq!(authorization, &format!("Bearer {TOKEN}"));
+    }
+}
+
+#[tokio::test]
+async fn rejected_upload_closes_the_changeset() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/api/0.6/", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        for status in ["200 OK", "409 Conflict", "200 OK"] {
+            let (mut stream, _) = listener.accept().unwrap();
+            let (request_line, _, _) = read_request(&mut stream);
+            let answer = if request_line.contains("create") {
+                "43"
+            } else {
+                "Version mismatch"
+            };
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{answer}",
+                answer.len()
+            )
+            .unwrap();
+        }
+    });
+
+    let mut session = session();
+    session
+        .edit("121486088:height=12".parse().unwrap())
+        .unwrap();
+    let uploaded = UploadClient::new(&api_url, TOKEN)
+        .upload(&session, "Height")
+        .await;
+    server.join().unwrap();
+    let error = uploaded.unwrap_err().to_string();
+    assert!(error.contains("409"), "{error}");
+}
@@ -0,0 +1,116 @@
+// Elevation of a tiny synthetic SRTM tile: 3x3 values, one void
+
+use geo::{LineString, MultiPolygon, Polygon};
+use osm_tb::{Elevation, GeographicCoordinates, GroundPosition, LocalProjection};
+
+static VOID: i16 = -32768;
+
+// Rows from north to south, big-endian. 258 is 0x0102, little-endian it would be 513
+fn tile_bytes() -> Vec<u8> {
+    [100, 200, 300, 400, VOID, 600, 700, 800, 258]
+        .into_iter()
+        .flat_map(i16::to_be_bytes)
+        .collect()
+}
+
+fn height(elevation: &Elevation, latitude: f64, longitude: f64) -> Option<f64> {
+    elevation.height_at(&GeographicCoordinates {
+        latitude,
+        longitude,
+    })
+}
+
+fn assert_height(elevation: &Elevation, latitude: f64, longitude: f64, expected: f64) {
+    let height = height(elevation, latitude, longitude).unwrap();
+    assert!(
+        (height - expected).abs() < 1e-3,
+        "{latitude} {longitude}: {height} != {expected}"
+    );
+}
+
+#[test]
+fn corners_and_samples() {
+    let mut elevation = Elevation::new();
+    elevation.add_hgt("N48E011.hgt", &tile_bytes());
+    assert!(!elevation.is_empty());
+
+    // The south west corner names the tile, the first row is the north edge
+    assert_height(&elevation, 48., 11., 700.);
+    assert_height(&elevation, 48., 11.5, 800.);
+    assert_height(&elevation, 48.5, 11., 400.);
+    assert_height(&elevation, 48.999_999_999, 11., 100.);
+    assert_height(&elevation, 48., 11.999_999_999, 258.);
+    assert_height(&elevation, 48., 11.75, (800. + 258.) / 2.);
+    // North and east of the tile is the next tile
+    assert_eq!(height(&elevation, 49., 11.5), None);
+    assert_eq!(height(&elevation, 48.5, 12.), None);
+    assert_eq!(height(&elevation, 47.5, 11.5), None);
+}
+
+#[test]
+fn void_values_are_left_out() {
+    let mut elevation = Elevation::new();
+    elevation.add_hgt("N48E011.hgt", &tile_bytes());
+    assert_height(&elevation, 48.75, 11.25, (100. + 200. + 400.) / 3.);
+    assert_height(&elevation, 48.25, 11.75, (600. + 800. + 258.) / 3.);
+    // Right on the void value
+    assert_eq!(height(&elevation, 48.5, 11.5), None);
+}
+
+#[test]
+fn southern_and_western_tiles() {
+    let mut elevation = Elevation::new();
+    elevation.add_hgt("S12W077.hgt", &tile_bytes());
+    assert_height(&elevation, -12., -77., 700.);
+    assert_height(&elevation, -11.5, -77., 400.);
+    assert_eq!(height(&elevation, 48., 11.), None);
+}
+
+#[test]
+fn bad_tiles_are_skipped() {
+    let mut elevation = Elevation::new();
+    elevation.add_hgt("N48E011.hgt", &tile_bytes()[..16]);
+    elevation.add_hgt("tile.hgt", &tile_bytes());
+    assert!(elevation.is_empty());
+}
+
+#[test]
+fn ground_is_the_lowest_corner() {
+    let mut elevation = Elevation::new();
+    elevation.add_hgt("N48E011.hgt", &tile_bytes());
+    let origin = GeographicCoordinates {
+        latitude: 48.25,
+        longitude: 11.5,
+    };
+    // Falling to the east. The southern corner is in a tile not loaded
+    let corners = [(-500., 0.), (500., 0.), (0., -50_000.)];
+    let outline = LineString::from(corners.to_vec());
+    let multipolygon = MultiPolygon(vec![Polygon::new(outline, vec![])]);
+    let ground = elevation
+        .ground_height(&LocalProjection::new(origin), &multipolygon)
+        .unwrap();
+
+    let [west, east, south] = corners.map(|(x, y)| {
+        let coordinates = origin.position_to_coordinates(GroundPosition { x, y });
+        elevation.height_above_origin(&origin, &coordinates)
+    });
+    assert_eq!(south, None);
+    assert!(east.unwrap() < west.unwrap());
+    assert_eq!(ground, east.unwrap());
+    assert!(ground < 0.);
+}
+
+#[test]
+fn geotiff_is_an_error() {
+    let directory = std::env::temp_dir().join("osm_tb_elevation_geotiff");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("N48E011.hgt"), tile_bytes()).unwrap();
+    std::fs::write(directory.join("readme.txt"), "tiles").unwrap();
+    let elevation = Elevation::from_directory(&directory).unwrap();
+    assert_height(&elevation, 48., 11.5, 800.);
+
+    std::fs::write(directory.join("N48E012.tif"), b"II*\0").unwrap();
+    let error = Elevation::from_directory(&directory).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Unsupported);
+}
@@ -0,0 +1,98 @@
+// The facade generator: levels of the wall and the windows and level lines on it
+
+use osm_tb::{
+    BuildingOrPart, FacadeQuad, GLASS_COLOR, GeographicCoordinates, GroundPosition, InputOsm,
+    WINDOW_COLOR, facade_quads, wall_levels,
+};
+
+// A building of about 12 x 7 meters with the given tags
+fn building(tags: &str) -> BuildingOrPart {
+    let json = format!(
+        r#"{{"elements":[
+        {{"type":"node","id":1,"lat":49.0,"lon":11.0}},
+        {{"type":"node","id":2,"lat":49.0,"lon":11.00016}},
+        {{"type":"node","id":3,"lat":49.00006,"lon":11.00016}},
+        {{"type":"node","id":4,"lat":49.00006,"lon":11.0}},
+        {{"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{{"building":"yes",{tags}}}}}
+    ]}}"#
+    );
+    let center = GeographicCoordinates {
+        latitude: 49.00003,
+        longitude: 11.00008,
+    };
+    let mut buildings_and_parts =
+        InputOsm::new().scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0);
+    assert_eq!(buildings_and_parts.len(), 1);
+    buildings_and_parts.remove(0)
+}
+
+// A wall of 12 meters along the x axis, facing south
+fn quads_of_wall(building_or_part: &BuildingOrPart, top: f64) -> Vec<FacadeQuad> {
+    facade_quads(
+        &GroundPosition { x: 0., y: 0. },
+        &GroundPosition { x: 12., y: 0. },
+        GroundPosition { x: 0., y: -1. },
+        building_or_part,
+        top,
+    )
+}
+
+#[test]
+fn levels_by_tags_or_by_the_height() {
+    let tagged = building(r#""building:levels":"3","height":"9""#);
+    assert_eq!(wall_levels(&tagged), (3, 3.));
+
+    // Not tagged: as many levels of 3 meters as fit
+    let (levels, level_height) = wall_levels(&building(r#""height":"10""#));
+    assert_eq!(levels, 3);
+    assert!((level_height - 10. / 3.).abs() < 1e-9);
+}
+
+#[test]
+fn windows_and_level_lines() {
+    let building_or_part = building(r#""building:levels":"3","height":"9""#);
+    let quads = quads_of_wall(&building_or_part, 9.);
+    let windows: Vec<_> = quads
+        .iter()
+        .filter(|quad| quad.color == WINDOW_COLOR)
+        .collect();
+    // 4 windows per level, a line between the levels
+    assert_eq!(windows.len(), 12);
+    assert_eq!(quads.len() - windows.len(), 2);
+    // The sill of the first level, y is up
+    assert!(
+        windows
+            .iter()
+            .any(|window| (window.down_left[1] - 0.9).abs() < 1e-5)
+    );
+
+    // The roof cuts the third level
+    assert_eq!(quads_of_wall(&building_or_part, 6.).len(), 8 + 1);
+    // Too short for a window
+    let short = facade_quads(
+        &GroundPosition { x: 0., y: 0. },
+        &GroundPosition { x: 1., y: 0. },
+        GroundPosition { x: 0., y: -1. },
+        &building_or_part,
+        9.,
+    );
+    assert!(short.is_empty());
+}
+
+#[test]
+fn glass_and_blind_facades() {
+    let glass = building(r#""building:levels":"3","height":"9","building:facade":"glass""#);
+    let quads = quads_of_wall(&glass, 9.);
+    assert_eq!(
+        quads
+            .iter()
+            .filter(|quad| quad.color == GLASS_COLOR)
+            .count(),
+        3
+    );
+    assert_eq!(quads.len(), 3 + 2);
+
+    let blind = building(r#""building:levels":"3","height":"9","window":"no""#);
+    let quads = quads_of_wall(&blind, 9.);
+    assert_eq!(quads.len(), 2); // only the level lines
+}
@@ -0,0 +1,222 @@
+// Regression tests: the stored OSM fixtures in assets/ through the whole pipeline,
+// compared with golden summaries in tests/golden/. A change in roofs or the subtraction
+// of parts shows up as a different count, bounding box or volume.
+//
+// After an intended change, write the new summaries and review their diff:
+//   OSM_TB_BLESS=1 cargo test --test golden
+// A new fixture: save the API answer of an element, like
+//   https://api.openstreetmap.org/api/0.6/map.json?bbox=... as assets/<name>.json
+// and add a test below. The reference buildings are listed in other_notes.md
+
+use std::path::PathBuf;
+
+use osm_tb::{
+    BuildingsAndParts, GeographicCoordinates, JsonData, OsmMeshAttributes,
+    scan_elements_from_layer_to_mesh, scan_json_to_osm,
+};
+
+static BLESS: &str = "OSM_TB_BLESS";
+static LENGTH_TOLERANCE: f64 = 0.01; // meters
+static VOLUME_TOLERANCE: f64 = 0.001; // share
+
+#[derive(Debug, PartialEq)]
+struct Summary {
+    buildings: usize,
+    parts: usize,
+    triangles: usize,
+    min: [f64; 3], // GPU axes: x east, y up, -z north
+    max: [f64; 3],
+    volume: f64, // cubic meters
+}
+
+impl Summary {
+    fn of(buildings_and_parts: &BuildingsAndParts, osm_meshes: &[OsmMeshAttributes]) -> Self {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        let mut triangles = 0;
+        let mut volume = 0.;
+        for mesh in osm_meshes {
+            for position in &mesh.vertices_positions {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(position[axis] as f64);
+                    max[axis] = max[axis].max(position[axis] as f64);
+                }
+            }
+            for triangle in mesh.indices_to_vertices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2]
+                    .map(|corner| mesh.vertices_positions[triangle[corner] as usize])
+                    .map(|position| position.map(|value| value as f64));
+                triangles += 1;
+                // Divergence theorem: the tetrahedrons from the GPU 0 position, which is
+                // on the ground, so the open bottoms add nothing
+                volume += (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                    + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                    / 6.;
+            }
+        }
+        let parts = buildings_and_parts
+            .iter()
+            .filter(|building_or_part| building_or_part.part)
+            .count();
+        Self {
+            buildings: buildings_and_parts.len() - parts,
+            parts,
+            triangles,
+            min,
+            max,
+            volume,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        let [min_x, min_y, min_z] = self.min;
+        let [max_x, max_y, max_z] = self.max;
+        format!(
+            "buildings {}\nparts {}\ntriangles {}\nmin {min_x:.2} {min_y:.2} {min_z:.2}\nmax {max_x:.2} {max_y:.2} {max_z:.2}\nvolume {:.1}\n",
+            self.buildings, self.parts, self.triangles, self.volume
+        )
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        let mut values = std::collections::HashMap::new();
+        for line in text.lines() {
+            let mut words = line.split_whitespace();
+            let key = words.next()?;
+            let numbers: Vec<f64> = words.map(|word| word.parse().ok()).collect::<Option<_>>()?;
+            values.insert(key, numbers);
+        }
+        let single = |key: &str| values.get(key)?.first().copied();
+        let triple =
+            |key: &str| -> Option<[f64; 3]> { values.get(key)?.as_slice().try_into().ok() };
+        Some(Self {
+            buildings: single("buildings")? as usize,
+            parts: single("parts")? as usize,
+            triangles: single("triangles")? as usize,
+            min: triple("min")?,
+            max: triple("max")?,
+            volume: single("volume")?,
+        })
+    }
+
+    // The differences to the golden summary, empty if none
+    fn differences(&self, golden: &Self) -> Vec<String> {
+        let mut differences = Vec::new();
+        for (name, value, expected) in [
+            ("buildings", self.buildings, golden.buildings),
+            ("parts", self.parts, golden.parts),
+            ("triangles", self.triangles, golden.triangles),
+        ] {
+            if value != expected {
+                differences.push(format!("{name}: {value}, expected {expected}"));
+            }
+        }
+        for axis in 0..3 {
+            for (name, value, expected) in [
+                ("min", self.min[axis], golden.min[axis]),
+                ("max", self.max[axis], golden.max[axis]),
+            ] {
+                if (value - expected).abs() > LENGTH_TOLERANCE {
+                    differences.push(format!(
+                        "{name}[{axis}]: {value:.2}, expected {expected:.2}"
+                    ));
+                }
+            }
+        }
+        if (self.volume - golden.volume).abs() > golden.volume.abs() * VOLUME_TOLERANCE + 1. {
+            differences.push(format!(
+                "volume: {:.1}, expected {:.1}",
+                self.volume, golden.volume
+            ));
+        }
+        differences
+    }
+}
+
+// The center of all nodes, like obi takes the center of the element
+fn center_of_nodes(bytes: &[u8]) -> GeographicCoordinates {
+    let json: serde_json::Value = serde_json::from_slice(bytes).expect("fixture is not JSON");
+    let (mut south, mut west) = (f64::INFINITY, f64::INFINITY);
+    let (mut north, mut east) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for element in json["elements"].as_array().into_iter().flatten() {
+        if let (Some(latitude), Some(longitude)) =
+            (element["lat"].as_f64(), element["lon"].as_f64())
+        {
+            south = south.min(latitude);
+            north = north.max(latitude);
+            west = west.min(longitude);
+            east = east.max(longitude);
+        }
+    }
+    GeographicCoordinates {
+        latitude: (south + north) / 2.,
+        longitude: (west + east) / 2.,
+    }
+}
+
+fn check_fixture(name: &str) {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let bytes =
+        std::fs::read(root.join("assets").join(format!("{name}.json"))).expect("fixture missing");
+    let json_data: JsonData = serde_json::from_slice(&bytes).expect("fixture is not OSM JSON");
+    let buildings_and_parts = scan_json_to_osm(json_data, &center_of_nodes(&bytes), 0, 0);
+    let osm_meshes = scan_elements_from_layer_to_mesh(buildings_and_parts.clone());
+    let summary = Summary::of(&buildings_and_parts, &osm_meshes);
+
+    let golden_file = root
+        .join("tests")
+        .join("golden")
+        .join(format!("{name}.txt"));
+    if std::env::var_os(BLESS).is_some() {
+        std::fs::write(&golden_file, summary.to_text()).expect("golden file not written");
+        return;
+    }
+    let golden = std::fs::read_to_string(&golden_file)
+        .ok()
+        .and_then(|text| Summary::from_text(&text))
+        .unwrap_or_else(|| panic!("no golden summary {golden_file:?}, run with {BLESS}=1"));
+    let differences = summary.differences(&golden);
+    assert!(
+        differences.is_empty(),
+        "{name} differs from its golden summary:\n{}\n\nnow:\n{}",
+        differences.join("\n"),
+        summary.to_text()
+    );
+}
+
+#[test]
+fn reifenberg_outline() {
+    check_fixture("way");
+}
+
+// Gabled, hipped, pyramidal and onion parts
+#[test]
+fn reifenberg_with_parts() {
+    check_fixture("bbox");
+}
+
+// Many parts, subtracted from the building
+#[test]
+fn st_pauls_cathedral() {
+    check_fixture("relation");
+}
+
+// A relation with an outer way
+#[test]
+fn bau_46_multipolygon() {
+    check_fixture("bau64");
+}
+
+#[test]
+fn summary_text_round_trip() {
+    let summary = Summary {
+        buildings: 1,
+        parts: 2,
+        triangles: 30,
+        min: [-1.5, 0., -2.25],
+        max: [4., 12.5, 3.],
+        volume: 321.5,
+    };
+    let read = Summary::from_text(&summary.to_text()).unwrap();
+    assert!(read.differences(&summary).is_empty());
+    assert_eq!(read, summary);
+}
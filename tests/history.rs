@@ -0,0 +1,106 @@
+// The history of a small building: a square, later a pentagon with a moved and a deleted node
+
+use osm_tb::{AsOf, history_as_of, history_versions};
+
+static HISTORY: &str = r#"{"elements":[
+{"type":"node","id":1,"lat":49.0,"lon":11.0,"timestamp":"2020-01-01T10:00:00Z","version":1,"visible":true},
+{"type":"node","id":2,"lat":49.0,"lon":11.0002,"timestamp":"2020-01-01T10:00:00Z","version":1,"visible":true},
+{"type":"node","id":2,"lat":49.0,"lon":11.0003,"timestamp":"2022-06-01T08:00:00Z","version":2,"visible":true},
+{"type":"node","id":3,"lat":49.0001,"lon":11.0002,"timestamp":"2020-01-01T10:00:00Z","version":1,"visible":true},
+{"type":"node","id":4,"lat":49.0001,"lon":11.0,"timestamp":"2020-01-01T10:00:00Z","version":1,"visible":true},
+{"type":"node","id":4,"timestamp":"2022-06-01T08:00:00Z","version":2,"visible":false},
+{"type":"node","id":5,"lat":49.00015,"lon":11.0001,"timestamp":"2022-06-01T08:00:00Z","version":1,"visible":true},
+{"type":"node","id":6,"lat":49.0001,"lon":11.0,"timestamp":"2022-06-01T08:00:00Z","version":1,"visible":true},
+{"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{"building":"yes"},"timestamp":"2020-01-01T10:00:00Z","version":1,"visible":true},
+{"type":"way","id":10,"nodes":[1,2,3,5,6,1],"tags":{"building":"house"},"timestamp":"2022-06-01T08:00:00Z","version":2,"visible":true}
+]}"#;
+
+fn elements(as_of: &AsOf) -> Vec<(String, u64, u64)> {
+    let bytes = history_as_of(HISTORY.as_bytes(), as_of, "way", 10).unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    json["elements"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|element| {
+            (
+                element["type"].as_str().unwrap().to_string(),
+                element["id"].as_u64().unwrap(),
+                element["version"].as_u64().unwrap(),
+            )
+        })
+        .collect()
+}
+
+fn element(element_type: &str, id: u64, version: u64) -> (String, u64, u64) {
+    (element_type.into(), id, version)
+}
+
+#[test]
+fn first_version() {
+    assert_eq!(
+        elements(&AsOf::Version(1)),
+        vec![
+            element("node", 1, 1),
+            element("node", 2, 1),
+            element("node", 3, 1),
+            element("node", 4, 1),
+            element("way", 10, 1),
+        ]
+    );
+}
+
+#[test]
+fn latest_without_the_deleted_node() {
+    assert_eq!(
+        elements(&AsOf::Latest),
+        vec![
+            element("node", 1, 1),
+            element("node", 2, 2),
+            element("node", 3, 1),
+            element("node", 5, 1),
+            element("node", 6, 1),
+            element("way", 10, 2),
+        ]
+    );
+}
+
+#[test]
+fn by_date() {
+    assert_eq!(
+        elements(&"2021-12-31".parse().unwrap()),
+        elements(&AsOf::Version(1))
+    );
+    assert_eq!(
+        elements(&"2022-06-01".parse().unwrap()),
+        elements(&AsOf::Latest)
+    );
+    assert!(elements(&"2019-01-01".parse().unwrap()).is_empty());
+}
+
+#[test]
+fn unknown_version() {
+    assert!(history_as_of(HISTORY.as_bytes(), &AsOf::Version(3), "way", 10).is_none());
+}
+
+#[test]
+fn versions() {
+    assert_eq!(
+        history_versions(HISTORY.as_bytes(), "way", 10),
+        vec![
+            (1, "2020-01-01T10:00:00Z".to_string()),
+            (2, "2022-06-01T08:00:00Z".to_string())
+        ]
+    );
+}
+
+#[test]
+fn parse() {
+    assert_eq!("latest".parse(), Ok(AsOf::Latest));
+    assert_eq!("7".parse(), Ok(AsOf::Version(7)));
+    assert_eq!(
+        "2023-05-22T12:26:48Z".parse(),
+        Ok(AsOf::Timestamp("2023-05-22T12:26:48Z".into()))
+    );
+    assert!("yesterday".parse::<AsOf>().is_err());
+}
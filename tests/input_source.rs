@@ -0,0 +1,51 @@
+// The sources of InputOsm: another API by its base URL, or a directory with fixtures
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::task::{Context, Poll, Waker};
+
+use osm_tb::{BoundingBox, GroundPosition, InputOsm};
+
+// Reading fixtures does not wait, one poll is enough. No tokio needed
+fn ready<F: Future>(future: F) -> F::Output {
+    let mut context = Context::from_waker(Waker::noop());
+    match pin!(future).poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("reading fixtures should not wait"),
+    }
+}
+
+fn assets() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("assets")
+}
+
+#[test]
+fn base_url() {
+    let api = InputOsm::new().with_base_url("https://master.apis.dev.openstreetmap.org/api/0.6");
+    assert!(api.directory().is_none());
+    assert_eq!(
+        api.element_url(42, true),
+        "https://master.apis.dev.openstreetmap.org/api/0.6/way/42/full.json"
+    );
+    let bounding_box = BoundingBox::new(GroundPosition::zero(), GroundPosition::zero());
+    assert!(
+        api.bbox_url(&bounding_box)
+            .starts_with("https://master.apis.dev.openstreetmap.org/api/0.6/map.json?bbox=")
+    );
+}
+
+#[test]
+fn fixture_directory() {
+    let api = InputOsm::from_directory(&assets());
+    assert_eq!(api.directory(), Some(assets().as_path()));
+    assert_eq!(
+        Path::new(&api.element_url(121486088, false)),
+        assets().join("relation.json")
+    );
+
+    let bounding_box = ready(api.geo_bbox_of_element(121486088, true)).unwrap();
+    assert!(bounding_box.width() > 0.);
+    let bytes = ready(api.load_osm(&bounding_box)).unwrap();
+    assert_eq!(bytes, std::fs::read(assets().join("bbox.json")).unwrap());
+}
@@ -0,0 +1,212 @@
+// Property tests of the length parser: many generated values, each checked against a rule.
+// The values come from a small seeded generator, so a failing case can be repeated.
+
+use osm_tb::{
+    GeographicCoordinates, InputOsm, LengthError, LengthUnit, METERS_PER_FOOT, METERS_PER_INCH,
+    parse_count, parse_length, parse_signed_length,
+};
+
+static CASES: usize = 2000;
+
+// xorshift64*
+struct Generator(u64);
+
+impl Generator {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, max: u64) -> u64 {
+        self.next() % max
+    }
+
+    // A value with up to two decimals, as used in OSM
+    fn value(&mut self) -> f64 {
+        self.below(100_000) as f64 / 100.
+    }
+}
+
+fn assert_meters(text: &str, expected: f64) {
+    let length = parse_length(text).unwrap_or_else(|error| panic!("{text:?}: {error}"));
+    assert!(
+        (length.meters - expected).abs() < 1e-9 * expected.max(1.),
+        "{text:?}: {} != {expected}",
+        length.meters
+    );
+}
+
+#[test]
+fn plain_and_meter_values() {
+    let mut generator = Generator(1);
+    for _ in 0..CASES {
+        let value = generator.value();
+        assert_meters(&format!("{value}"), value);
+        assert_meters(&format!("{value}m"), value);
+        assert_meters(&format!("{value} m"), value);
+        assert_meters(&format!(" {value} metres "), value);
+        assert_eq!(
+            parse_length(&format!("{value}")).unwrap().unit,
+            LengthUnit::None
+        );
+        assert_eq!(
+            parse_length(&format!("{value} m")).unwrap().unit,
+            LengthUnit::Meter
+        );
+    }
+}
+
+#[test]
+fn decimal_comma_is_decimal_point() {
+    let mut generator = Generator(2);
+    for _ in 0..CASES {
+        let value = generator.value();
+        let text = format!("{value}");
+        if !text.contains('.') {
+            continue;
+        }
+        assert_meters(&text.replace('.', ","), value);
+        assert_meters(&format!("{} m", text.replace('.', ",")), value);
+    }
+}
+
+#[test]
+fn metric_units() {
+    let mut generator = Generator(3);
+    for _ in 0..CASES {
+        let value = generator.value();
+        assert_meters(&format!("{value} cm"), value / 100.);
+        assert_meters(&format!("{value}mm"), value / 1000.);
+    }
+}
+
+#[test]
+fn feet_and_inches() {
+    let mut generator = Generator(4);
+    for _ in 0..CASES {
+        let feet = generator.below(400);
+        let inches = generator.below(12);
+        let expected = feet as f64 * METERS_PER_FOOT + inches as f64 * METERS_PER_INCH;
+        assert_meters(&format!("{feet} ft"), feet as f64 * METERS_PER_FOOT);
+        assert_meters(&format!("{feet}'"), feet as f64 * METERS_PER_FOOT);
+        assert_meters(&format!("{inches}\""), inches as f64 * METERS_PER_INCH);
+        assert_meters(&format!("{feet}'{inches}\""), expected);
+        assert_meters(&format!("{feet}' {inches}''"), expected);
+        assert_meters(&format!("{feet} ft {inches} in"), expected);
+        assert_eq!(
+            parse_length(&format!("{feet}'{inches}\"")).unwrap().unit,
+            LengthUnit::FootInch
+        );
+    }
+}
+
+#[test]
+fn lists_and_ranges_use_the_largest_value() {
+    let mut generator = Generator(5);
+    for _ in 0..CASES {
+        let a = generator.value();
+        let b = generator.value();
+        for text in [format!("{a};{b}"), format!("{a}-{b}"), format!("{a} - {b}")] {
+            assert_meters(&text, a.max(b));
+            assert!(parse_length(&text).unwrap().ambiguous, "{text:?}");
+        }
+        // The unit of one value counts for all
+        assert_meters(&format!("{a}-{b} cm"), a.max(b) / 100.);
+        assert!(!parse_length(&format!("{a}")).unwrap().ambiguous);
+    }
+}
+
+#[test]
+fn negative_values_are_errors() {
+    let mut generator = Generator(6);
+    for _ in 0..CASES {
+        let value = generator.value() + 0.01;
+        assert_eq!(
+            parse_length(&format!("-{value}")),
+            Err(LengthError::Negative)
+        );
+        assert_eq!(
+            parse_count(&format!("-{value}")),
+            Err(LengthError::Negative)
+        );
+    }
+}
+
+#[test]
+fn signed_values_below_the_ground() {
+    let mut generator = Generator(8);
+    for _ in 0..CASES {
+        let value = generator.value() + 0.01;
+        let length = parse_signed_length(&format!("-{value} m")).unwrap();
+        assert!((length.meters + value).abs() < 1e-9 * value.max(1.));
+        assert_eq!(
+            parse_signed_length(&format!("{value}")).unwrap().meters,
+            value
+        );
+    }
+    let feet_inches = parse_signed_length("-5'6\"").unwrap().meters;
+    assert!((feet_inches + 5. * METERS_PER_FOOT + 6. * METERS_PER_INCH).abs() < 1e-9);
+}
+
+#[test]
+fn min_height_below_the_ground() {
+    let json = r#"{"elements":[
+        {"type":"node","id":1,"lat":49.0,"lon":11.0},
+        {"type":"node","id":2,"lat":49.0,"lon":11.0001},
+        {"type":"node","id":3,"lat":49.0001,"lon":11.0001},
+        {"type":"way","id":10,"nodes":[1,2,3,1],
+         "tags":{"building":"yes","min_height":"-5","height":"10"}}
+    ]}"#;
+    let center = GeographicCoordinates {
+        latitude: 49.0,
+        longitude: 11.0,
+    };
+    let buildings_and_parts = InputOsm::new().scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0);
+    assert_eq!(buildings_and_parts[0].min_height, -5.);
+    assert_eq!(buildings_and_parts[0].wall_height, 10.);
+}
+
+#[test]
+fn level_counts() {
+    let mut generator = Generator(7);
+    for _ in 0..CASES {
+        let a = generator.below(200) as f64;
+        let b = generator.below(200) as f64;
+        assert_eq!(parse_count(&format!("{a}")), Ok(a));
+        assert_eq!(parse_count(&format!("{a};{b}")), Ok(a.max(b)));
+        assert_eq!(parse_count(&format!("{a},5")), Ok(a + 0.5));
+        assert!(parse_count(&format!("{a} m")).is_err());
+    }
+}
+
+#[test]
+fn known_errors() {
+    assert_eq!(parse_length(""), Err(LengthError::Empty));
+    assert_eq!(parse_length("  "), Err(LengthError::Empty));
+    assert_eq!(parse_length("tall"), Err(LengthError::NotANumber));
+    assert_eq!(parse_length("inf"), Err(LengthError::NotANumber));
+    assert_eq!(parse_length("NaN"), Err(LengthError::NotANumber));
+    assert_eq!(parse_length("12 furlong"), Err(LengthError::UnknownUnit));
+    assert_eq!(parse_length("6\"12'"), Err(LengthError::UnknownUnit));
+    assert_eq!(parse_length("1,000,000"), Err(LengthError::NotANumber));
+}
+
+#[test]
+fn never_panics_and_results_are_finite() {
+    let alphabet: Vec<char> = "0123456789 .,;-+'\"mcftinhx".chars().collect();
+    let mut generator = Generator(8);
+    for _ in 0..CASES * 10 {
+        let length = generator.below(12) as usize;
+        let text: String = (0..length)
+            .map(|_| alphabet[generator.below(alphabet.len() as u64) as usize])
+            .collect();
+        if let Ok(length) = parse_length(&text) {
+            assert!(length.meters.is_finite() && length.meters >= 0., "{text:?}");
+        }
+        if let Ok(count) = parse_count(&text) {
+            assert!(count.is_finite() && count >= 0., "{text:?}");
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// The level height model: level heights by the building type, building:min_level and conflicts
+
+use osm_tb::{BuildingOrPart, GeographicCoordinates, InputOsm, level_height_by_building_type};
+
+// A building of about 12 x 7 meters with the given tags
+fn building(tags: &str) -> BuildingOrPart {
+    let json = format!(
+        r#"{{"elements":[
+        {{"type":"node","id":1,"lat":49.0,"lon":11.0}},
+        {{"type":"node","id":2,"lat":49.0,"lon":11.00016}},
+        {{"type":"node","id":3,"lat":49.00006,"lon":11.00016}},
+        {{"type":"node","id":4,"lat":49.00006,"lon":11.0}},
+        {{"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{{{tags}}}}}
+    ]}}"#
+    );
+    let center = GeographicCoordinates {
+        latitude: 49.00003,
+        longitude: 11.00008,
+    };
+    let mut buildings_and_parts =
+        InputOsm::new().scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0);
+    assert_eq!(buildings_and_parts.len(), 1);
+    buildings_and_parts.remove(0)
+}
+
+fn assert_close(value: f64, expected: f64) {
+    assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+}
+
+#[test]
+fn level_heights_by_building_type() {
+    for (building, expected) in [
+        ("house", 2.8),
+        ("apartments", 3.0),
+        ("office", 3.5),
+        ("industrial", 5.0),
+        ("church", 6.0),
+        ("garage", 2.5),
+        ("yes", 3.0),
+    ] {
+        assert_close(
+            level_height_by_building_type(Some(&building.to_string())),
+            expected,
+        );
+    }
+    assert_close(level_height_by_building_type(None), 3.0);
+}
+
+#[test]
+fn levels_make_the_height() {
+    let house = building(r#""building":"house","building:levels":"2","roof:shape":"flat""#);
+    assert_close(house.level_height, 2.8);
+    assert_close(house.wall_height, 5.6);
+
+    let industrial = building(r#""building":"industrial","building:levels":"2""#);
+    assert_close(industrial.wall_height, 10.);
+}
+
+#[test]
+fn tagged_height_and_levels_make_the_level_height() {
+    let building_or_part = building(r#""building":"yes","building:levels":"4","height":"14""#);
+    assert_close(building_or_part.level_height, 3.5);
+    assert_close(building_or_part.wall_height, 14.);
+
+    // 25 meters per level is a conflict, the type tells the level height
+    let conflict = building(r#""building":"office","building:levels":"2","height":"50""#);
+    assert_close(conflict.level_height, 3.5);
+    assert_close(conflict.wall_height, 50.);
+}
+
+#[test]
+fn min_level_sets_the_min_height() {
+    let building_or_part = building(
+        r#""building":"office","building:levels":"3","building:min_level":"1","roof:shape":"flat""#,
+    );
+    assert_close(building_or_part.min_height, 3.5);
+    assert_close(building_or_part.wall_height, 10.5);
+}
@@ -0,0 +1,185 @@
+// The mesh checks with small hand made meshes, each with one known problem
+
+use osm_tb::{
+    GeographicCoordinates, GroundFeature, GroundPosition, GroundShape, InputOsm, MeshCheckOptions,
+    MeshProblemKind, OsmMeshAttributes, RenderPosition, check_meshes, check_meshes_with_options,
+    ground_to_mesh, scan_elements_from_layer_to_mesh,
+};
+
+static CLOSED: MeshCheckOptions = MeshCheckOptions {
+    open_bottoms: false,
+};
+
+fn mesh(positions: &[RenderPosition], indices: &[u32]) -> OsmMeshAttributes {
+    OsmMeshAttributes {
+        indices_to_vertices: indices.to_vec(),
+        vertices_colors: vec![[1.; 4]; positions.len()],
+        vertices_positions: positions.to_vec(),
+    }
+}
+
+// Counter clockwise seen from outside
+fn tetrahedron() -> OsmMeshAttributes {
+    let corners = [[0., 0., 0.], [1., 0., 0.], [0., 0., -1.], [0., 1., 0.]];
+    let faces = [[0, 1, 3], [1, 2, 3], [2, 0, 3], [0, 2, 1]];
+    // The corners are not reused, like in the renderer
+    let mut positions = Vec::new();
+    for face in faces {
+        for corner in face {
+            positions.push(corners[corner]);
+        }
+    }
+    mesh(&positions, &(0..12).collect::<Vec<u32>>())
+}
+
+fn kinds(osm_meshes: &[OsmMeshAttributes]) -> Vec<MeshProblemKind> {
+    check_meshes_with_options(osm_meshes, &CLOSED)
+        .iter()
+        .map(|problem| problem.kind)
+        .collect()
+}
+
+#[test]
+fn closed_mesh_has_no_problems() {
+    assert_eq!(kinds(&[tetrahedron()]), vec![]);
+}
+
+#[test]
+fn inverted_face() {
+    let mut inverted = tetrahedron();
+    inverted.indices_to_vertices.swap(0, 1);
+    let kinds = kinds(&[inverted]);
+    assert_eq!(kinds.len(), 3); // each edge of the face
+    assert!(
+        kinds
+            .iter()
+            .all(|kind| *kind == MeshProblemKind::InconsistentWinding)
+    );
+}
+
+#[test]
+fn missing_face() {
+    let mut open = tetrahedron();
+    open.indices_to_vertices.truncate(9);
+    assert_eq!(kinds(&[open]), vec![MeshProblemKind::OpenEdge; 3]);
+}
+
+#[test]
+fn index_out_of_range() {
+    let mut broken = tetrahedron();
+    broken.indices_to_vertices[11] = 99;
+    let problems = check_meshes_with_options(&[broken], &CLOSED);
+    assert_eq!(problems[0].kind, MeshProblemKind::IndexOutOfRange);
+    assert_eq!(problems[0].triangle, 3);
+}
+
+#[test]
+fn degenerate_triangle() {
+    let line = mesh(&[[0., 0., 0.], [1., 0., 0.], [2., 0., 0.]], &[0, 1, 2]);
+    assert_eq!(kinds(&[line]), vec![MeshProblemKind::Degenerate]);
+}
+
+#[test]
+fn z_fighting() {
+    // Two roofs at the same height, facing up, half over each other
+    let roofs = mesh(
+        &[
+            [0., 3., 0.],
+            [2., 3., 0.],
+            [0., 3., -2.],
+            [1., 3., 0.],
+            [3., 3., 0.],
+            [1., 3., -2.],
+        ],
+        &[0, 1, 2, 3, 4, 5],
+    );
+    let problems = check_meshes(&[roofs]);
+    let fighting: Vec<_> = problems
+        .iter()
+        .filter(|problem| problem.kind == MeshProblemKind::ZFighting)
+        .collect();
+    assert_eq!(fighting.len(), 1);
+    assert_eq!(fighting[0].triangle, 1);
+}
+
+#[test]
+fn wall_bottoms_may_be_open() {
+    // A wall on the ground, two triangles
+    let wall = mesh(
+        &[
+            [0., 0., 0.],
+            [1., 0., 0.],
+            [1., 2., 0.],
+            [0., 0., 0.],
+            [1., 2., 0.],
+            [0., 2., 0.],
+        ],
+        &[0, 1, 2, 3, 4, 5],
+    );
+    let open = |options| {
+        check_meshes_with_options(std::slice::from_ref(&wall), &options)
+            .iter()
+            .filter(|problem| problem.kind == MeshProblemKind::OpenEdge)
+            .count()
+    };
+    assert_eq!(open(CLOSED), 4);
+    assert_eq!(open(MeshCheckOptions::default()), 3);
+}
+
+// Divergence theorem with the field (x, 0, 0): the volume enclosed by the triangles, also
+// of tubes without top and bottom. Negative if the faces point inwards
+fn volume_by_x(mesh: &OsmMeshAttributes, below: f32) -> f32 {
+    let mut volume = 0.;
+    for triangle in mesh.indices_to_vertices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|corner| mesh.vertices_positions[triangle[corner] as usize]);
+        if a[1].max(b[1]).max(c[1]) > below {
+            continue;
+        }
+        let normal_x = (b[1] - a[1]) * (c[2] - a[2]) - (b[2] - a[2]) * (c[1] - a[1]);
+        volume += (a[0] + b[0] + c[0]) / 3. * normal_x / 2.;
+    }
+    volume
+}
+
+#[test]
+fn tree_faces_outwards() {
+    let tree = ground_to_mesh(&vec![GroundFeature {
+        id: 1,
+        shape: GroundShape::Tree {
+            position: GroundPosition { x: 3., y: -2. },
+            height: 10.,
+            crown_diameter: 4.,
+        },
+        color: [1.; 4],
+        level: 0.,
+    }]);
+    // Trunk 0.4 x 0.4 up to 3.5 m, crown 4 x 4 above
+    let volume = 0.4 * 0.4 * 3.5 + 4. * 4. * 6.5;
+    assert!((volume_by_x(&tree, f32::INFINITY) - volume).abs() < 0.01);
+    assert!(
+        check_meshes(&[tree])
+            .iter()
+            .all(|problem| problem.kind != MeshProblemKind::InconsistentWinding)
+    );
+}
+
+#[test]
+fn pillars_face_outwards() {
+    // A 4 m high roof on 4 pillars, about 11 x 11 m
+    let json = r#"{"elements":[
+        {"type":"node","id":1,"lat":49.0,"lon":11.0},
+        {"type":"node","id":2,"lat":49.0,"lon":11.00015},
+        {"type":"node","id":3,"lat":49.0001,"lon":11.00015},
+        {"type":"node","id":4,"lat":49.0001,"lon":11.0},
+        {"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{"building":"roof","height":"4"}}
+    ]}"#;
+    let center = GeographicCoordinates {
+        latitude: 49.00005,
+        longitude: 11.000075,
+    };
+    let buildings_and_parts = InputOsm::new().scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0);
+    let meshes = scan_elements_from_layer_to_mesh(buildings_and_parts);
+    // Below the roof slab, 0.3 m thick, only the pillars and the flat bottom of the slab
+    let pillars = 4. * 0.3 * 0.3 * 3.7;
+    assert!((volume_by_x(&meshes[0], 3.75) - pillars).abs() < 0.01);
+}
@@ -0,0 +1,177 @@
+// osmChange files applied to the Reifenberg chapel of assets/way.json
+
+use std::path::PathBuf;
+
+use osm_tb::{ChangeAction, GeographicCoordinates, InputOsm, OsmChange, color_changes};
+
+static CHAPEL: u64 = 121486088;
+
+// A moved node of the chapel and a new shed, with the negative ids of an editor
+static CHANGE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<osmChange version="0.6" generator="JOSM">
+<!-- saved, not uploaded -->
+<modify>
+  <node id="1359856313" version="8" lat="49.7559402" lon="11.1358696"/>
+</modify>
+<create>
+  <node id="-1" lat="49.7561" lon="11.1361"/>
+  <node id="-2" lat="49.7561" lon="11.1362"/>
+  <node id="-3" lat="49.7562" lon="11.1362"/>
+  <way id="-4">
+    <nd ref="-1"/><nd ref="-2"/><nd ref="-3"/><nd ref="-1"/>
+    <tag k="building" v="shed"/>
+    <tag k="name" v="Tom &amp; Jerry&#39;s &quot;Hut&quot;"/>
+  </way>
+</create>
+</osmChange>
+"#;
+
+static DELETE: &str = r#"<osmChange version="0.6">
+<delete><way id="121486088" version="5"/></delete>
+</osmChange>"#;
+
+fn chapel_bytes() -> Vec<u8> {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    std::fs::read(root.join("assets").join("way.json")).unwrap()
+}
+
+fn center() -> GeographicCoordinates {
+    GeographicCoordinates {
+        latitude: 49.756,
+        longitude: 11.136,
+    }
+}
+
+#[test]
+fn read_osc() {
+    let osm_change = OsmChange::from_xml(CHANGE).unwrap();
+    assert_eq!(osm_change.to_string(), "4 created, 1 modified, 0 deleted");
+    let (action, shed) = &osm_change.elements[4];
+    assert_eq!(*action, ChangeAction::Create);
+    assert_eq!(shed["id"].as_u64(), Some(u64::MAX - 3));
+    assert_eq!(shed["nodes"][0].as_u64(), Some(u64::MAX));
+    assert_eq!(shed["tags"]["name"], "Tom & Jerry's \"Hut\"");
+}
+
+#[test]
+fn broken_osc() {
+    assert!(OsmChange::from_xml("<osmChange><node id=\"1\"/></osmChange>").is_err());
+    assert!(OsmChange::from_xml("<create><node id=\"1 lat=\"2\"/></create>").is_err());
+    assert!(OsmChange::from_xml("<create><way id=\"x\"/></create>").is_err());
+}
+
+#[test]
+fn created_and_modified() {
+    let osm_change = OsmChange::from_xml(CHANGE).unwrap();
+    let before = chapel_bytes();
+    let after = osm_change.apply(&before).unwrap();
+
+    let actions = osm_change.element_actions(&before);
+    assert_eq!(actions.get(&CHAPEL), Some(&ChangeAction::Modify));
+    let actions = osm_change.element_actions(&after);
+    assert_eq!(actions.get(&(u64::MAX - 3)), Some(&ChangeAction::Create));
+
+    let mut buildings_and_parts = InputOsm::new().scan_json_to_osm_vec(&after, &center(), 0, 0);
+    assert_eq!(buildings_and_parts.len(), 2);
+    color_changes(&mut buildings_and_parts, &actions);
+    let shed = buildings_and_parts
+        .iter()
+        .find(|building| building.id == u64::MAX - 3)
+        .unwrap();
+    let chapel = buildings_and_parts
+        .iter()
+        .find(|building| building.id == CHAPEL)
+        .unwrap();
+    assert_ne!(shed.building_color, chapel.building_color);
+}
+
+#[test]
+fn deleted() {
+    let osm_change = OsmChange::from_xml(DELETE).unwrap();
+    let before = chapel_bytes();
+    let after = osm_change.apply(&before).unwrap();
+    assert_eq!(
+        osm_change.element_actions(&before).get(&CHAPEL),
+        Some(&ChangeAction::Delete)
+    );
+    assert!(
+        InputOsm::new()
+            .scan_json_to_osm_vec(&after, &center(), 0, 0)
+            .is_empty()
+    );
+}
+
+// A changeset download: a moved node, a created shed and a deleted garage, with the versions
+// after the upload. The loaded data is after it too
+static DOWNLOAD: &str = r#"<osmChange version="0.6" generator="OpenStreetMap server">
+<modify>
+  <node id="1" changeset="42" version="2" lat="49.0" lon="11.0001"/>
+</modify>
+<create>
+  <node id="5" changeset="42" version="1" lat="49.001" lon="11.0"/>
+  <node id="6" changeset="42" version="1" lat="49.001" lon="11.0001"/>
+  <node id="7" changeset="42" version="1" lat="49.0011" lon="11.0"/>
+  <way id="11" changeset="42" version="1">
+    <nd ref="5"/><nd ref="6"/><nd ref="7"/><nd ref="5"/>
+    <tag k="building" v="shed"/>
+  </way>
+</create>
+<delete>
+  <way id="12" changeset="42" version="3" visible="false"/>
+  <node id="8" changeset="42" version="2" visible="false"/>
+</delete>
+</osmChange>"#;
+
+static AFTER: &str = r#"{"elements":[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0001,"version":2},
+    {"type":"node","id":2,"lat":49.0,"lon":11.0002,"version":1},
+    {"type":"node","id":3,"lat":49.0001,"lon":11.0002,"version":1},
+    {"type":"node","id":5,"lat":49.001,"lon":11.0,"version":1},
+    {"type":"node","id":6,"lat":49.001,"lon":11.0001,"version":1},
+    {"type":"node","id":7,"lat":49.0011,"lon":11.0,"version":1},
+    {"type":"node","id":9,"lat":49.002,"lon":11.0001,"version":1},
+    {"type":"node","id":10,"lat":49.0021,"lon":11.0,"version":1},
+    {"type":"way","id":10,"nodes":[1,2,3,1],"version":1,"tags":{"building":"yes"}},
+    {"type":"way","id":11,"nodes":[5,6,7,5],"version":1,"tags":{"building":"shed"}}
+]}"#;
+
+// As the API sends them for nodes.json?nodes=1v1,8v1 and ways.json?ways=12v2
+static PREVIOUS: &str = r#"[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0,"version":1},
+    {"type":"node","id":8,"lat":49.002,"lon":11.0,"version":1},
+    {"type":"way","id":12,"nodes":[8,9,10,8],"version":2,"tags":{"building":"garage"}}
+]"#;
+
+#[test]
+fn uploaded_changeset() {
+    let osm_change = OsmChange::from_xml(DOWNLOAD).unwrap();
+    assert_eq!(
+        osm_change.previous_versions(),
+        [
+            ("node".to_string(), 1, 1),
+            ("way".to_string(), 12, 2),
+            ("node".to_string(), 8, 1)
+        ]
+    );
+    let previous: Vec<serde_json::Value> = serde_json::from_str(PREVIOUS).unwrap();
+    let before = osm_change.revert(AFTER.as_bytes(), &previous).unwrap();
+
+    let ids = |bytes: &[u8]| {
+        let mut ids: Vec<u64> = InputOsm::new()
+            .scan_json_to_osm_vec(bytes, &center(), 0, 0)
+            .iter()
+            .map(|building| building.id)
+            .collect();
+        ids.sort();
+        ids
+    };
+    assert_eq!(ids(AFTER.as_bytes()), [10, 11]);
+    assert_eq!(ids(&before), [10, 12]);
+
+    let actions = osm_change.element_actions(&before);
+    assert_eq!(actions.get(&10), Some(&ChangeAction::Modify));
+    assert_eq!(actions.get(&12), Some(&ChangeAction::Delete));
+    // The moved node is back
+    let json: serde_json::Value = serde_json::from_slice(&before).unwrap();
+    assert!(json["elements"].as_array().unwrap().contains(&previous[0]));
+}
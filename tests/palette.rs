@@ -0,0 +1,51 @@
+// The colour palette: names, light and dark prefixes and the alpha of the parsed colours
+
+use osm_tb::Palette;
+
+#[test]
+fn light_and_dark_keep_the_alpha() {
+    let palette = Palette::default();
+    for value in [
+        "light_grey",
+        "Light Red",
+        "lightblue",
+        "dark-green",
+        "light_brick",
+    ] {
+        let color = palette
+            .parse_color(value)
+            .unwrap_or_else(|| panic!("{value}"));
+        assert_eq!(color[3], 1., "{value}");
+        assert!(
+            color.iter().all(|channel| (0. ..=1.).contains(channel)),
+            "{value}"
+        );
+    }
+}
+
+#[test]
+fn channels_are_clamped() {
+    // The light factor of 1.3 would bring white above 1
+    let white = Palette::default().parse_color("light_white").unwrap();
+    assert_eq!(white, [1., 1., 1., 1.]);
+
+    let palette = Palette::from_json(
+        r##"{"light": 3.0, "dark": 0.5, "colors": {"pale": "#808080"}, "materials": {}}"##,
+    )
+    .unwrap();
+    let light = palette.parse_color("light_pale").unwrap();
+    assert_eq!(light, [1., 1., 1., 1.]);
+    let dark = palette.parse_color("dark_pale").unwrap();
+    assert!((dark[0] - 0.251).abs() < 0.01);
+    assert_eq!(dark[3], 1.);
+}
+
+#[test]
+fn alpha_of_css_values() {
+    let palette = Palette::default();
+    let half = palette.parse_color("#ff000080").unwrap();
+    assert!((half[3] - 0.5).abs() < 0.01);
+    let opaque = palette.parse_color("rgba(255, 0, 0, 2)").unwrap();
+    assert_eq!(opaque[3], 1.);
+    assert!(palette.parse_color("no colour").is_none());
+}
@@ -0,0 +1,61 @@
+// The SVG plan view of a building with parts inside, overlapping and outside of it
+
+use osm_tb::{GeographicCoordinates, InputOsm, PartCoverage, scan_elements_from_layer_to_svg};
+
+// Building 10 with the gabled part 20 inside, part 30 half inside and part 40 beside it
+static MAP: &str = r#"{"elements":[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0},
+    {"type":"node","id":2,"lat":49.0,"lon":11.0004},
+    {"type":"node","id":3,"lat":49.0002,"lon":11.0004},
+    {"type":"node","id":4,"lat":49.0002,"lon":11.0},
+    {"type":"node","id":5,"lat":49.00005,"lon":11.0001},
+    {"type":"node","id":6,"lat":49.00005,"lon":11.0003},
+    {"type":"node","id":7,"lat":49.00015,"lon":11.0003},
+    {"type":"node","id":8,"lat":49.00015,"lon":11.0001},
+    {"type":"node","id":9,"lat":49.0,"lon":11.0003},
+    {"type":"node","id":10,"lat":49.0,"lon":11.0005},
+    {"type":"node","id":11,"lat":49.0002,"lon":11.0005},
+    {"type":"node","id":12,"lat":49.0002,"lon":11.0003},
+    {"type":"node","id":13,"lat":49.0,"lon":11.0006},
+    {"type":"node","id":14,"lat":49.0,"lon":11.0008},
+    {"type":"node","id":15,"lat":49.0001,"lon":11.0008},
+    {"type":"node","id":16,"lat":49.0001,"lon":11.0006},
+    {"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{"building":"yes","height":"6"}},
+    {"type":"way","id":20,"nodes":[5,6,7,8,5],
+        "tags":{"building:part":"yes","height":"12","roof:shape":"gabled","roof:height":"3"}},
+    {"type":"way","id":30,"nodes":[9,10,11,12,9],"tags":{"building:part":"yes","height":"9"}},
+    {"type":"way","id":40,"nodes":[13,14,15,16,13],"tags":{"building:part":"yes","height":"3"}}
+]}"#;
+
+#[test]
+fn parts_inside_overlapping_and_outside() {
+    let center = GeographicCoordinates {
+        latitude: 49.0001,
+        longitude: 11.0004,
+    };
+    let buildings_and_parts = InputOsm::new().scan_json_to_osm_vec(MAP.as_bytes(), &center, 0, 0);
+    let coverage = |id: u64| {
+        buildings_and_parts
+            .iter()
+            .find(|building_or_part| building_or_part.id == id)
+            .map(|building_or_part| building_or_part.coverage)
+    };
+    assert_eq!(coverage(10), Some(PartCoverage::Inside));
+    assert_eq!(coverage(20), Some(PartCoverage::Inside));
+    assert!(matches!(
+        coverage(30),
+        Some(PartCoverage::Overlapping { .. })
+    ));
+    assert_eq!(coverage(40), Some(PartCoverage::Outside));
+
+    let svg = scan_elements_from_layer_to_svg(&buildings_and_parts);
+    for title in ["building 10", "part 20", "part 30", "part 40"] {
+        assert!(svg.contains(&format!("<title>{title}</title>")), "{title}");
+    }
+    // Only the gabled part has a ridge
+    assert_eq!(svg.matches("<line ").count(), 1);
+    assert_eq!(svg.matches("stroke-dasharray").count(), 2);
+    assert!(svg.contains("30 (50% outside)"));
+    assert!(svg.contains("40 (100% outside)"));
+    assert!(svg.contains(">20</text>"));
+}
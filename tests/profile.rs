@@ -0,0 +1,102 @@
+// The interpretation profiles: the same tags, read like different 3D renderers do
+
+use osm_tb::{
+    ALL_PROFILES, BuildingRemains, BuildingsAndParts, DEFAULT_WALL_HEIGHT, GeographicCoordinates,
+    InputOsm, InterpretationProfile, RoofColorDefault,
+};
+
+// Building 10 of about 12 x 7 meters. Part 20 covers its west half, if tagged
+fn scan(profile: InterpretationProfile, tags: &str, with_part: bool) -> BuildingsAndParts {
+    let part = if with_part {
+        r#",{"type":"way","id":20,"nodes":[1,5,6,4,1],"tags":{"building:part":"yes"}}"#
+    } else {
+        ""
+    };
+    let json = format!(
+        r#"{{"elements":[
+        {{"type":"node","id":1,"lat":49.0,"lon":11.0}},
+        {{"type":"node","id":2,"lat":49.0,"lon":11.00016}},
+        {{"type":"node","id":3,"lat":49.00006,"lon":11.00016}},
+        {{"type":"node","id":4,"lat":49.00006,"lon":11.0}},
+        {{"type":"node","id":5,"lat":49.0,"lon":11.00008}},
+        {{"type":"node","id":6,"lat":49.00006,"lon":11.00008}},
+        {{"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{{{tags}}}}}{part}
+    ]}}"#
+    );
+    let center = GeographicCoordinates {
+        latitude: 49.00003,
+        longitude: 11.00008,
+    };
+    InputOsm::new()
+        .with_profile(profile)
+        .scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0)
+}
+
+#[test]
+fn rules_of_the_profiles() {
+    let rules = ALL_PROFILES.map(|profile| profile.rules());
+    assert_eq!(
+        rules.map(|rules| rules.building_remains),
+        [
+            BuildingRemains::MinPercent(20),
+            BuildingRemains::Hidden,
+            BuildingRemains::MinPercent(1),
+            BuildingRemains::Hidden,
+        ]
+    );
+    assert_eq!(
+        rules.map(|rules| rules.roof_color),
+        [
+            RoofColorDefault::Guess,
+            RoofColorDefault::Neutral,
+            RoofColorDefault::InheritFromBuilding,
+            RoofColorDefault::ByRoofShape,
+        ]
+    );
+    for profile in ALL_PROFILES {
+        assert_eq!(profile.name().parse::<InterpretationProfile>(), Ok(profile));
+    }
+    assert!("blender".parse::<InterpretationProfile>().is_err());
+}
+
+#[test]
+fn default_wall_heights() {
+    let heights = ALL_PROFILES.map(|profile| {
+        let buildings_and_parts = scan(profile, r#""building":"yes""#, false);
+        buildings_and_parts[0].wall_height
+    });
+    assert_eq!(
+        heights,
+        [DEFAULT_WALL_HEIGHT, DEFAULT_WALL_HEIGHT, 3.0, 7.5]
+    );
+}
+
+#[test]
+fn implied_roof_shapes() {
+    let shapes = ALL_PROFILES.map(|profile| {
+        let house = scan(profile, r#""building":"house""#, false);
+        let unknown = scan(
+            profile,
+            r#""building":"yes","roof:shape":"onion_dome""#,
+            false,
+        );
+        format!("{:?} {:?}", house[0].roof_shape, unknown[0].roof_shape)
+    });
+    assert_eq!(
+        shapes,
+        ["Flat Gabled", "Flat Flat", "Flat Flat", "Gabled Gabled"]
+    );
+}
+
+#[test]
+fn remains_of_buildings_with_parts() {
+    let ids = ALL_PROFILES.map(|profile| {
+        let mut ids: Vec<u64> = scan(profile, r#""building":"yes""#, true)
+            .iter()
+            .map(|building_or_part| building_or_part.id)
+            .collect();
+        ids.sort();
+        ids
+    });
+    assert_eq!(ids, [vec![10, 20], vec![20], vec![10, 20], vec![20]]);
+}
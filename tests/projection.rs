@@ -0,0 +1,62 @@
+// The local projection: meters to lat/lon and back, at several latitudes and up to 20 km away
+
+use osm_tb::{GeographicCoordinates, GroundPosition, LocalProjection};
+
+static ORIGINS: [(f64, f64); 4] = [(0., 0.), (51.5, -0.12), (-33.9, 151.2), (70., 25.)];
+static OFFSETS: [f64; 7] = [-20_000., -5_000., -100., 0., 1., 2_500., 20_000.];
+
+#[test]
+fn inverse_then_forward_is_the_same_position() {
+    for (latitude, longitude) in ORIGINS {
+        let projection = LocalProjection::new(GeographicCoordinates {
+            latitude,
+            longitude,
+        });
+        for x in OFFSETS {
+            for y in OFFSETS {
+                let coordinates = projection.inverse(GroundPosition { x, y });
+                let back = projection.forward(coordinates.latitude, coordinates.longitude);
+                let error = (back.x - x).hypot(back.y - y);
+                assert!(error < 1e-3, "{latitude} {longitude} {x} {y}: {error} m");
+            }
+        }
+    }
+}
+
+#[test]
+fn forward_then_inverse_is_the_same_place() {
+    for (latitude, longitude) in ORIGINS {
+        let projection = LocalProjection::new(GeographicCoordinates {
+            latitude,
+            longitude,
+        });
+        // About 20 km at most
+        for d_lat in [-0.18, -0.01, 0., 0.05, 0.18] {
+            for d_lon in [-0.18, 0., 0.02, 0.18] {
+                let d_lon = d_lon / latitude.to_radians().cos();
+                let position = projection.forward(latitude + d_lat, longitude + d_lon);
+                let coordinates = projection.inverse(position);
+                let again = projection.forward(coordinates.latitude, coordinates.longitude);
+                assert!((again.x - position.x).hypot(again.y - position.y) < 1e-3);
+                // 1e-8 degrees are about a millimeter
+                assert!((coordinates.latitude - latitude - d_lat).abs() < 1e-8);
+                assert!((coordinates.longitude - longitude - d_lon).abs() < 1e-8);
+            }
+        }
+    }
+}
+
+#[test]
+fn origin_and_axes() {
+    let projection = LocalProjection::new(GeographicCoordinates {
+        latitude: 51.5,
+        longitude: -0.12,
+    });
+    let origin = projection.forward(51.5, -0.12);
+    assert!(origin.x.abs() < 1e-6 && origin.y.abs() < 1e-6);
+    // One thousandth of a degree north is about 111 m, east about 69 m
+    let north = projection.forward(51.501, -0.12);
+    assert!(north.x.abs() < 1e-3 && (north.y - 111.3).abs() < 0.1);
+    let east = projection.forward(51.5, -0.119);
+    assert!((east.x - 69.4).abs() < 0.1 && east.y.abs() < 0.01);
+}
@@ -0,0 +1,209 @@
+// The response cache on disk, in a directory of its own per test
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, mpsc};
+
+use osm_tb::{InputOsm, ResponseCache, element_versions, iso_time};
+
+static URL: &str = "https://api.openstreetmap.org/api/0.6/way/121486088/full.json";
+
+fn cache_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("osm_tb_cache_{name}"));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+#[test]
+fn written_responses_are_read() {
+    let directory = cache_directory("read");
+    let cache = ResponseCache::new(&directory);
+    assert!(cache.read(URL).is_none());
+
+    cache
+        .write(URL, b"{\"elements\":[]}", Some("\"42\""))
+        .unwrap();
+    let cached = cache.read(URL).unwrap();
+    assert_eq!(&cached.bytes[..], b"{\"elements\":[]}");
+    assert_eq!(cached.etag.as_deref(), Some("\"42\""));
+    assert!(cache.read(&URL.replace("full", "history")).is_none());
+}
+
+#[test]
+fn refresh_ignores_the_cached() {
+    let directory = cache_directory("refresh");
+    ResponseCache::new(&directory)
+        .write(URL, b"{}", None)
+        .unwrap();
+    assert!(
+        ResponseCache::new(&directory)
+            .with_refresh(true)
+            .read(URL)
+            .is_none()
+    );
+}
+
+#[test]
+fn versions_of_the_fixture() {
+    let root = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let bytes = std::fs::read(root.join("assets").join("way.json")).unwrap();
+    let versions = element_versions(&bytes);
+    assert_eq!(versions.get(&("node".into(), 1359856313)), Some(&7));
+    assert!(element_versions(b"no json").is_empty());
+}
+
+#[test]
+fn times_as_the_api_writes_them() {
+    assert_eq!(iso_time(0), "1970-01-01T00:00:00Z");
+    assert_eq!(iso_time(951_868_799), "2000-02-29T23:59:59Z");
+    assert_eq!(iso_time(1_684_758_408), "2023-05-22T12:26:48Z");
+}
+
+static FULL: &str = r#"{"elements":[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0,"version":1},
+    {"type":"node","id":2,"lat":49.0,"lon":11.0001,"version":1},
+    {"type":"node","id":3,"lat":49.0001,"lon":11.0,"version":1},
+    {"type":"way","id":10,"nodes":[1,2,3,1],"version":1,"tags":{"building":"yes"}}
+]}"#;
+static WAYS: &str = r#"{"elements":[
+    {"type":"way","id":10,"nodes":[1,2,3,1],"version":1,"tags":{"building":"yes"}}
+]}"#;
+static NODES: &str = r#"{"elements":[
+    {"type":"node","id":1,"lat":49.0,"lon":11.0,"version":1},
+    {"type":"node","id":2,"lat":49.0,"lon":11.0001,"version":1},
+    {"type":"node","id":3,"lat":49.0001,"lon":11.0,"version":1}
+]}"#;
+
+type Answers = Arc<Mutex<Vec<(&'static str, String)>>>;
+
+// Answers each request by the start of its path, the answers may change between requests
+fn mock_api(listener: TcpListener, answers: Answers, sender: mpsc::Sender<String>) {
+    for stream in listener.incoming() {
+        let mut stream = stream.unwrap();
+        let mut request_line = String::new();
+        BufReader::new(&mut stream)
+            .read_line(&mut request_line)
+            .unwrap();
+        let path = request_line
+            .split(' ')
+            .nth(1)
+            .unwrap_or_default()
+            .to_string();
+        let answer = answers
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(start, _)| path.starts_with(start))
+            .map(|(_, answer)| answer.clone());
+        if sender.send(path).is_err() {
+            break;
+        }
+        let status = if answer.is_some() {
+            "200 OK"
+        } else {
+            "404 Not Found"
+        };
+        let answer = answer.unwrap_or_default();
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{answer}",
+            answer.len()
+        )
+        .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn moved_nodes_invalidate_the_cached() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/api/0.6/", listener.local_addr().unwrap());
+    let answers: Answers = Arc::new(Mutex::new(vec![
+        ("/api/0.6/way/10/full.json", FULL.into()),
+        ("/api/0.6/ways.json?ways=10", WAYS.into()),
+        ("/api/0.6/nodes.json?nodes=1,2,3", NODES.into()),
+    ]));
+    let (sender, receiver) = mpsc::channel();
+    let server_answers = answers.clone();
+    std::thread::spawn(move || mock_api(listener, server_answers, sender));
+
+    let input = InputOsm::new()
+        .with_base_url(&api_url)
+        .with_cache(ResponseCache::new(&cache_directory("validity")));
+    let bounding_box = input.geo_bbox_of_element(10, true).await.unwrap();
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(requests, ["/api/0.6/way/10/full.json"]);
+
+    // Unchanged: only the versions are loaded
+    assert_eq!(
+        input.geo_bbox_of_element(10, true).await.unwrap(),
+        bounding_box
+    );
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(
+        requests,
+        [
+            "/api/0.6/nodes.json?nodes=1,2,3",
+            "/api/0.6/ways.json?ways=10"
+        ]
+    );
+
+    // A moved node changes only its own version, not the one of the way
+    let moved = |json: &str| {
+        json.replace(
+            r#""id":2,"lat":49.0,"lon":11.0001,"version":1"#,
+            r#""id":2,"lat":49.0,"lon":11.0002,"version":2"#,
+        )
+    };
+    answers.lock().unwrap()[0].1 = moved(FULL);
+    answers.lock().unwrap()[2].1 = moved(NODES);
+    let moved_box = input.geo_bbox_of_element(10, true).await.unwrap();
+    assert!(moved_box.width() > bounding_box.width());
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(requests.last().unwrap(), "/api/0.6/way/10/full.json");
+
+    // A deleted node fails the multi fetch
+    answers.lock().unwrap().remove(2);
+    input.geo_bbox_of_element(10, true).await.unwrap();
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(requests.last().unwrap(), "/api/0.6/way/10/full.json");
+}
+
+#[tokio::test]
+async fn changesets_in_the_area_invalidate_the_cached() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let api_url = format!("http://{}/api/0.6/", listener.local_addr().unwrap());
+    let answers: Answers = Arc::new(Mutex::new(vec![
+        ("/api/0.6/map.json", FULL.into()),
+        ("/api/0.6/changesets.json", r#"{"changesets":[]}"#.into()),
+    ]));
+    let (sender, receiver) = mpsc::channel();
+    let server_answers = answers.clone();
+    std::thread::spawn(move || mock_api(listener, server_answers, sender));
+
+    let input = InputOsm::new()
+        .with_base_url(&api_url)
+        .with_cache(ResponseCache::new(&cache_directory("area")));
+    let bounding_box = osm_tb::BoundingBox::new((11.0, 49.0), (11.001, 49.001));
+    input.load_osm(&bounding_box).await.unwrap();
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(requests, ["/api/0.6/map.json?bbox=11,49,11.001,49.001"]);
+
+    // No changeset since: the cached area, whatever its elements are
+    assert_eq!(
+        &input.load_osm(&bounding_box).await.unwrap()[..],
+        FULL.as_bytes()
+    );
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].starts_with("/api/0.6/changesets.json?bbox=11,49,11.001,49.001&time="));
+
+    // An edit of a neighbour is a changeset in the area
+    answers.lock().unwrap()[1].1 = r#"{"changesets":[{"id":1}]}"#.into();
+    input.load_osm(&bounding_box).await.unwrap();
+    let requests: Vec<String> = receiver.try_iter().collect();
+    assert_eq!(
+        requests.last().unwrap(),
+        "/api/0.6/map.json?bbox=11,49,11.001,49.001"
+    );
+}
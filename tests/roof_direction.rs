@@ -0,0 +1,135 @@
+// Snapping of roof:direction to the closest edge normal of the footprint
+
+use osm_tb::{
+    ANGLE_SNAP_TOLERANCE, BuildingOrPart, COMPASS_SNAP_TOLERANCE, Footprint, GeographicCoordinates,
+    GroundPosition, InputOsm, RoofSnap,
+};
+
+static METERS_PER_DEGREE: f64 = 111_320.;
+
+// The corners of a 20 x 10 meters rectangle around 0,0, turned clockwise by the degrees
+fn corners(degrees: f64) -> Vec<GroundPosition> {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    [(-10., -5.), (10., -5.), (10., 5.), (-10., 5.)]
+        .iter()
+        .map(|(x, y)| GroundPosition {
+            x: x * cos + y * sin,
+            y: -x * sin + y * cos,
+        })
+        .collect()
+}
+
+fn footprint(degrees: f64) -> Footprint {
+    let mut footprint = Footprint::new();
+    for corner in corners(degrees) {
+        footprint.push_position(corner);
+    }
+    footprint.close();
+    footprint
+}
+
+// A skillion roof on the turned rectangle with the tagged roof:direction
+fn building(degrees: f64, direction: &str) -> BuildingOrPart {
+    let center = GeographicCoordinates {
+        latitude: 49.,
+        longitude: 11.,
+    };
+    let mut elements = Vec::new();
+    for (index, corner) in corners(degrees).iter().enumerate() {
+        elements.push(format!(
+            r#"{{"type":"node","id":{},"lat":{},"lon":{}}}"#,
+            index + 1,
+            center.latitude + corner.y / METERS_PER_DEGREE,
+            center.longitude + corner.x / METERS_PER_DEGREE / center.latitude.to_radians().cos(),
+        ));
+    }
+    let direction = if direction.is_empty() {
+        String::new()
+    } else {
+        format!(r#","roof:direction":"{direction}""#)
+    };
+    elements.push(format!(
+        r#"{{"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{{"building":"yes","roof:shape":"skillion"{direction}}}}}"#
+    ));
+    let json = format!(r#"{{"elements":[{}]}}"#, elements.join(","));
+    let mut buildings_and_parts =
+        InputOsm::new().scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0);
+    assert_eq!(buildings_and_parts.len(), 1);
+    buildings_and_parts.remove(0)
+}
+
+fn assert_degrees(radians: f64, expected: f64) {
+    assert!(
+        (radians.to_degrees() - expected).abs() < 0.5,
+        "{} != {expected}",
+        radians.to_degrees()
+    );
+}
+
+#[test]
+fn snap_within_the_tolerance() {
+    let compass = COMPASS_SNAP_TOLERANCE.to_radians();
+    let east = 90_f64.to_radians();
+    assert_degrees(
+        footprint(5.).snap_to_edge_normal(east, compass).unwrap(),
+        95.,
+    );
+    assert_degrees(
+        footprint(-10.).snap_to_edge_normal(east, compass).unwrap(),
+        80.,
+    );
+    assert!(footprint(20.).snap_to_edge_normal(east, compass).is_none());
+
+    let angle = ANGLE_SNAP_TOLERANCE.to_radians();
+    assert!(footprint(3.).snap_to_edge_normal(east, angle).is_some());
+    assert!(footprint(8.).snap_to_edge_normal(east, angle).is_none());
+    // Both sides of an edge count
+    let west = 270_f64.to_radians();
+    assert_degrees(
+        footprint(5.).snap_to_edge_normal(west, angle).unwrap(),
+        275.,
+    );
+}
+
+#[test]
+fn short_edges_give_no_direction() {
+    // The north east corner cut by an edge of 0.5 meters, its normal is north east
+    let mut footprint = Footprint::new();
+    for (x, y) in [(-10., -5.), (10., -5.), (10., 4.65), (9.65, 5.), (-10., 5.)] {
+        footprint.push_position(GroundPosition { x, y });
+    }
+    footprint.close();
+    let normal = footprint
+        .snap_to_edge_normal(45_f64.to_radians(), 50_f64.to_radians())
+        .unwrap();
+    assert!(normal.to_degrees().round() % 90. == 0.);
+}
+
+#[test]
+fn the_snapping_is_in_the_output() {
+    let compass = building(5., "E");
+    assert_eq!(
+        compass.roof_snap,
+        RoofSnap::Snapped {
+            tagged_angle: 90_f64.to_radians()
+        }
+    );
+    assert_degrees(compass.roof_angle, 95.);
+
+    let turned = building(20., "E");
+    assert_eq!(turned.roof_snap, RoofSnap::Unsnapped);
+    assert_degrees(turned.roof_angle, 90.);
+
+    // Degrees are meant more exactly than compass directions
+    assert!(matches!(
+        building(3., "93").roof_snap,
+        RoofSnap::Snapped { .. }
+    ));
+    assert_eq!(building(10., "90").roof_snap, RoofSnap::Unsnapped);
+    assert!(matches!(
+        building(10., "E").roof_snap,
+        RoofSnap::Snapped { .. }
+    ));
+
+    assert_eq!(building(5., "").roof_snap, RoofSnap::NotTagged);
+}
@@ -0,0 +1,81 @@
+// The CPU renderer: a building from OSM JSON and a ground reaching behind the camera
+
+use osm_tb::{
+    GeographicCoordinates, InputOsm, OsmMeshAttributes, SnapshotCamera, render_snapshot,
+    scan_elements_from_layer_to_mesh,
+};
+
+static SKY_COLOR: [u8; 3] = [128, 128, 255];
+static GROUND_COLOR: [u8; 3] = [150, 255, 150];
+
+fn camera() -> SnapshotCamera {
+    SnapshotCamera {
+        distance: 60.,
+        width: 80,
+        height: 60,
+        ..Default::default()
+    }
+}
+
+fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+    let index = ((y * width + x) * 4) as usize;
+    [
+        pixels[index],
+        pixels[index + 1],
+        pixels[index + 2],
+        pixels[index + 3],
+    ]
+}
+
+#[test]
+fn building_on_the_background() {
+    let json = r#"{"elements":[
+        {"type":"node","id":1,"lat":49.0,"lon":11.0},
+        {"type":"node","id":2,"lat":49.0,"lon":11.00015},
+        {"type":"node","id":3,"lat":49.0001,"lon":11.00015},
+        {"type":"node","id":4,"lat":49.0001,"lon":11.0},
+        {"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{"building":"yes","height":"10"}}
+    ]}"#;
+    let center = GeographicCoordinates {
+        latitude: 49.00005,
+        longitude: 11.000075,
+    };
+    let buildings_and_parts = InputOsm::new().scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0);
+    let meshes = scan_elements_from_layer_to_mesh(buildings_and_parts);
+    let snapshot = render_snapshot(&meshes, &camera());
+
+    assert_eq!((snapshot.width, snapshot.height), (80, 60));
+    assert_eq!(snapshot.pixels.len(), 80 * 60 * 4);
+    // Looking down, the corner shows the ground, not the sky
+    let corner = pixel(&snapshot.pixels, 80, 0, 0);
+    assert_eq!(
+        corner,
+        [GROUND_COLOR[0], GROUND_COLOR[1], GROUND_COLOR[2], 255]
+    );
+    // The building in the center, lit by the sun
+    let building = pixel(&snapshot.pixels, 80, 40, 30);
+    assert_eq!(building[3], 255);
+    assert_ne!(building[..3], GROUND_COLOR);
+    assert_ne!(building[..3], SKY_COLOR);
+}
+
+#[test]
+fn ground_behind_the_camera_is_clipped() {
+    // A red square of 2 km, counter clockwise seen from above, with the camera above it
+    let size = 1000.;
+    let ground = OsmMeshAttributes {
+        vertices_positions: vec![
+            [-size, 0., size],
+            [size, 0., size],
+            [size, 0., -size],
+            [-size, 0., -size],
+        ],
+        vertices_colors: vec![[1., 0., 0., 1.]; 4],
+        indices_to_vertices: vec![0, 1, 2, 0, 2, 3],
+    };
+    let snapshot = render_snapshot(&[ground], &camera());
+    for (x, y) in [(0, 0), (40, 30), (79, 59)] {
+        let [r, g, b, _] = pixel(&snapshot.pixels, 80, x, y);
+        assert!(r > 200 && g == 0 && b == 0, "{x},{y}: {r} {g} {b}");
+    }
+}
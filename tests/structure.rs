@@ -0,0 +1,57 @@
+// Buildings and parts, which are not walls with a roof: roofs only, thin roofs and steps
+
+use osm_tb::{BuildingOrPart, GeographicCoordinates, InputOsm, Structure};
+
+// A building of about 12 x 7 meters with the given tags.
+// A part gets a building with the same outline around it
+fn building(tags: &str) -> BuildingOrPart {
+    let json = format!(
+        r#"{{"elements":[
+        {{"type":"node","id":1,"lat":49.0,"lon":11.0}},
+        {{"type":"node","id":2,"lat":49.0,"lon":11.00016}},
+        {{"type":"node","id":3,"lat":49.00006,"lon":11.00016}},
+        {{"type":"node","id":4,"lat":49.00006,"lon":11.0}},
+        {{"type":"way","id":10,"nodes":[1,2,3,4,1],"tags":{{{tags}}}}},
+        {{"type":"way","id":20,"nodes":[1,2,3,4,1],"tags":{{"building":"yes"}}}}
+    ]}}"#
+    );
+    let center = GeographicCoordinates {
+        latitude: 49.00003,
+        longitude: 11.00008,
+    };
+    InputOsm::new()
+        .scan_json_to_osm_vec(json.as_bytes(), &center, 0, 0)
+        .into_iter()
+        .find(|building_or_part| building_or_part.id == 10)
+        .unwrap()
+}
+
+#[test]
+fn roof_only_buildings() {
+    let carport = building(r#""building":"roof","height":"3""#);
+    assert_eq!(carport.structure, Structure::RoofOnly);
+}
+
+#[test]
+fn thin_roofs() {
+    let roof_part = building(r#""building:part":"roof","height":"8","min_height":"6""#);
+    assert_eq!(roof_part.structure, Structure::ThinRoof);
+
+    // The F4map trick: the min_height at the top of the wall, only the roof is left
+    let floating = building(
+        r#""building:part":"yes","height":"10","min_height":"7.8","roof:shape":"gabled","roof:height":"2""#,
+    );
+    assert_eq!(floating.structure, Structure::ThinRoof);
+}
+
+#[test]
+fn walls_with_a_roof() {
+    for tags in [
+        r#""building":"yes","height":"10""#,
+        r#""building:part":"yes","height":"10","min_height":"6""#,
+        // At the ground, a low wall is still a wall
+        r#""building":"yes","height":"0.3""#,
+    ] {
+        assert_eq!(building(tags).structure, Structure::Solid, "{tags}");
+    }
+}